@@ -1,13 +1,15 @@
 use crate::consts::*;
+use crate::layouts::LaneTexture;
+use crate::score::Judgement;
 use crate::time::ControlledTime;
 use crate::types::*;
-use crate::user_settings::UserSettings;
+use crate::user_settings::{LnMode, UserSettings};
 use crate::ScoreResource;
 // use bevy::audio::*;
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 use bevy_kira_audio::prelude::*;
-use bms_rs::lex::command::ObjId;
-use ordered_float::OrderedFloat;
+use std::collections::{HashMap, VecDeque};
 
 /// Keeps the textures and materials for Bars
 #[derive(Resource)]
@@ -41,7 +43,72 @@ pub struct GameplayUI;
 struct Bar {
     position: Positions,
     // audio_source: Handle<AudioSource>,
-    audio_source_id: ObjId,
+    audio_source_id: SoundId,
+    note_type: NoteType,
+    /// Chart time in seconds at which the bar reaches the judge line
+    hit_time: f64,
+    /// Height of the long note body in pixels, 0 for normal notes
+    body_length: f32,
+}
+
+/// The stretched sprite between a long note's head and tail
+#[derive(Component)]
+struct LongNoteBody;
+
+/// Marks a long note whose head was hit and is now being held
+#[derive(Component)]
+struct HeldNote;
+
+/// Which keysound a press in each lane plays when there is no note to hit
+#[derive(Resource, Default)]
+struct LaneKeysounds {
+    /// Spawned notes that haven't been judged yet, oldest first
+    upcoming: HashMap<Positions, VecDeque<(Entity, SoundId)>>,
+    /// Song time and keysound of the last note hit in each lane
+    last_hit: HashMap<Positions, (f64, SoundId)>,
+}
+
+impl LaneKeysounds {
+    fn spawned(&mut self, position: Positions, entity: Entity, id: SoundId) {
+        self.upcoming
+            .entry(position)
+            .or_default()
+            .push_back((entity, id));
+    }
+
+    /// Forgets a note that was missed or judged
+    fn passed(&mut self, position: Positions, entity: Entity) {
+        if let Some(upcoming) = self.upcoming.get_mut(&position) {
+            upcoming.retain(|(upcoming_entity, _)| *upcoming_entity != entity);
+        }
+    }
+
+    fn hit(&mut self, position: Positions, entity: Entity, id: SoundId, secs: f64) {
+        self.passed(position, entity);
+        self.last_hit.insert(position, (secs, id));
+    }
+
+    /// Picks the keysound for an empty press, like LR2 and beatoraja do: an invisible note placed
+    /// since the last hit, then the nearest upcoming note, then the last note hit
+    fn empty_press_keysound(
+        &self,
+        position: Positions,
+        invisible: Option<(f64, SoundId)>,
+    ) -> Option<SoundId> {
+        let last_hit = self.last_hit.get(&position);
+
+        if let Some((invisible_time, invisible_id)) = invisible {
+            if last_hit.is_none_or(|(hit_time, _)| invisible_time > *hit_time) {
+                return Some(invisible_id);
+            }
+        }
+
+        self.upcoming
+            .get(&position)
+            .and_then(|upcoming| upcoming.front())
+            .map(|(_, id)| *id)
+            .or(last_hit.map(|(_, id)| *id))
+    }
 }
 
 #[derive(Resource)]
@@ -56,17 +123,19 @@ fn despawn_ui(mut commands: Commands, query: Query<(Entity, &GameplayUI)>) {
     }
 }
 
-fn setup_target_bars(mut commands: Commands, materials: Res<BarMaterialResource>) {
+fn setup_target_bars(
+    mut commands: Commands,
+    materials: Res<BarMaterialResource>,
+    song_config: Res<SongConfig>,
+) {
     println!("setting up target bars");
-    let bar_width = 100.;
-    let bar_offset = 400.;
+    commands.insert_resource(LaneKeysounds::default());
 
-    for n in 1..=7 {
-        let transform = Transform::from_translation(Vec3::new(
-            n as f32 * bar_width - bar_offset,
-            TARGET_POSITION,
-            1.,
-        ));
+    let layout = &song_config.layout;
+    let bar_width = layout.lane_width;
+
+    for lane in &layout.lanes {
+        let transform = Transform::from_translation(Vec3::new(lane.x, TARGET_POSITION, 1.));
 
         commands
             .spawn(SpriteBundle {
@@ -93,19 +162,13 @@ fn play_bgms(
     // This might be wrong bc of travel time of the note...
     // Notes are spawning after 3 seconds, but they don't play until
     // they are clicked
-    let secs = time.seconds_since_startup() - 3.75;
+    let secs = song_time(&time);
     let secs_last = secs - time.delta_seconds_f64();
 
     // let mut remove_counter = 0;
     for bgm in &song_config.bgms {
         if secs_last < bgm.spawn_time && bgm.spawn_time <= secs {
             for id in &bgm.audio_source_ids {
-                // get handle from map
-                let audio_handle = song_config
-                    .audio_handles
-                    .get(&id)
-                    .expect("Could not find bgm audio handle in map");
-
                 // play sound -- do this somehwere else?
                 // commands.spawn(AudioBundle {
                 //     source: audio_handle.clone(),
@@ -115,7 +178,7 @@ fn play_bgms(
                 //     },
                 //     ..default()
                 // });
-                audio.play(audio_handle.clone()).with_volume(VOLUME);
+                song_config.play_sound(&audio, *id);
             }
             // TODO this does not work
             // remove_counter += 1;
@@ -132,56 +195,85 @@ fn spawn_bars(
     mut song_config: ResMut<SongConfig>,
     materials: Res<BarMaterialResource>,
     time: Res<ControlledTime>,
+    settings: Res<UserSettings>,
+    mut lane_keysounds: ResMut<LaneKeysounds>,
     // mut timer: ResMut<SpawnTimer>,
 ) {
-    // We get the current time since startup (secs) and the time since the last iterations (secs_last),
-    // this way we check if any bars should spawn in this window
+    let secs = song_time(&time);
 
-    // Song starts 3 seconds after start, so we subtract 3 seconds
-    let secs = time.seconds_since_startup() - 3.;
-    let secs_last = secs - time.delta_seconds_f64();
+    // Bars spawn one travel time ahead of the judge line, measured in scroll time so
+    // that bars which will sit through a stop spawn correspondingly later
+    let scroll_secs = song_config.scroll_time(secs) + travel_time(&settings);
 
     // Counter of how many bars we need to spawn and remove from the list
     let mut remove_counter = 0;
     for bar in &song_config.notes {
         // List is ordered, so we can just check until an item fails
-        // Check if bar should be spawned at any point between last frame and this frame
-        // println!(
-        //     "secs_last={}, spawn_time={}, secs={}",
-        //     secs_last, bar.spawn_time, secs
-        // );
-        if secs_last < bar.spawn_time && bar.spawn_time <= secs {
+        if song_config.scroll_time(bar.spawn_time) <= scroll_secs {
             remove_counter += 1;
 
             // Get the correct material according to position
-            let material = match bar.position {
-                Positions::One | Positions::Three | Positions::Five | Positions::Seven => {
-                    materials.blue_texture.clone()
-                }
-                _ => materials.white_texture.clone(),
+            let material = match song_config.layout.texture(bar.position) {
+                LaneTexture::Blue => materials.blue_texture.clone(),
+                LaneTexture::White => materials.white_texture.clone(),
+            };
+
+            // Mines are tinted so they can't be mistaken for notes in the same lane
+            let (material, color) = match bar.note_type {
+                NoteType::Mine { .. } => (materials.white_texture.clone(), Color::RED),
+                _ => (material, Color::WHITE),
             };
 
-            let bar_width = 100.;
+            let bar_width = song_config.layout.lane_width;
             // let bar_x_pos = bar.position.x() as f32 * bar_width - 400.;
-            let bar_x_pos = bar.position.x();
-
-            // let transform = Transform::from_translation(Vec3::new(-400., SPAWN_POSITION, 1.));
-            let transform = Transform::from_translation(Vec3::new(bar_x_pos, SPAWN_POSITION, 1.));
-            commands
-                .spawn(SpriteBundle {
-                    texture: material,
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(bar_width, 30.)),
-                        ..default()
-                    },
-                    transform,
+            let bar_x_pos = song_config.layout.x(bar.position);
+
+            let body_length = body_length(&song_config, bar.spawn_time, bar.note_type, &settings);
+            let bar_y_pos = bar_y(&song_config, bar.spawn_time, secs, &settings);
+
+            let transform = Transform::from_translation(Vec3::new(bar_x_pos, bar_y_pos, 1.));
+            let mut bar_entity = commands.spawn(SpriteBundle {
+                texture: material.clone(),
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(bar_width, 30.)),
                     ..default()
-                })
+                },
+                transform,
+                ..default()
+            });
+            bar_entity
                 .insert(Bar {
                     position: bar.position,
                     audio_source_id: bar.audio_source_id.to_owned(),
+                    note_type: bar.note_type,
+                    hit_time: bar.spawn_time,
+                    body_length,
                 })
                 .insert(GameplayUI);
+
+            if !matches!(bar.note_type, NoteType::Mine { .. }) {
+                lane_keysounds.spawned(bar.position, bar_entity.id(), bar.audio_source_id);
+            }
+
+            // The body is a child so it moves with the head, stretching up to the tail
+            if let NoteType::Long { .. } = bar.note_type {
+                bar_entity.with_children(|parent| {
+                    parent
+                        .spawn(SpriteBundle {
+                            texture: material,
+                            sprite: Sprite {
+                                color: Color::rgba(1., 1., 1., 0.6),
+                                custom_size: Some(Vec2::new(bar_width * 0.8, body_length)),
+                                anchor: Anchor::BottomCenter,
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(0., 0., -0.1)),
+                            ..default()
+                        })
+                        .insert(LongNoteBody);
+                });
+            }
         } else {
             break;
         }
@@ -193,122 +285,293 @@ fn spawn_bars(
     }
 }
 
-/// Moves the bars downward
+/// Places the bars by how far their hit time is from the current song time, so they stay in sync
+/// through frame hitches and scroll speed changes, and hold still while a stop is in effect.
+/// Held long notes stay on the judge line, with only the part left to hold above it.
 fn move_bars(
     time: Res<ControlledTime>,
-    mut query: Query<(&mut Transform, &Bar)>,
+    mut query: Query<(&mut Transform, &mut Bar, Option<&Children>, Has<HeldNote>)>,
+    mut bodies: Query<&mut Sprite, (With<LongNoteBody>, Without<Bar>)>,
+    song_config: Res<SongConfig>,
     settings: Res<UserSettings>,
 ) {
-    for (mut transform, _bar) in query.iter_mut() {
-        transform.translation.y -= time.delta_seconds() * settings.scroll_speed;
+    let secs = song_time(&time);
+
+    for (mut transform, mut bar, children, held) in query.iter_mut() {
+        let head_time = if held {
+            bar.hit_time.max(secs)
+        } else {
+            bar.hit_time
+        };
+        transform.translation.y = bar_y(&song_config, head_time, secs, &settings);
+
+        if let NoteType::Long { .. } = bar.note_type {
+            bar.body_length = body_length(&song_config, head_time, bar.note_type, &settings);
+
+            let Some(children) = children else {
+                continue;
+            };
+            for child in children.iter() {
+                if let Ok(mut sprite) = bodies.get_mut(*child) {
+                    sprite.custom_size = Some(Vec2::new(
+                        song_config.layout.lane_width * 0.8,
+                        bar.body_length,
+                    ));
+                }
+            }
+        }
     }
 }
 
-/// Despawns bars when they reach the end if the correct button is clicked
+/// Judges bars by how far the press was from their hit time, and despawns the ones that were missed
+#[allow(clippy::too_many_arguments)]
 fn despawn_bars(
     mut commands: Commands,
-    query: Query<(Entity, &Transform, &Bar)>,
-    mut song_config: ResMut<SongConfig>,
+    query: Query<(Entity, &Bar), Without<HeldNote>>,
+    song_config: Res<SongConfig>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut score: ResMut<ScoreResource>,
     audio: Res<Audio>,
     settings: Res<UserSettings>,
+    time: Res<ControlledTime>,
+    mut lane_keysounds: ResMut<LaneKeysounds>,
 ) {
-    let mut notes_in_threshold: Vec<(Entity, f32, &Bar)> = Vec::new();
+    let secs = song_time(&time);
+    let windows = &settings.judge_windows;
+    // Oldest note in each pressed lane that is close enough to be hit
+    let mut lane_notes: HashMap<Positions, (Entity, f32, &Bar)> = HashMap::new();
+    // Lanes with a note too early to hit but close enough that pressing is an empty POOR
+    let mut approaching: Vec<Positions> = Vec::new();
+
+    for (entity, bar) in query.iter() {
+        // Mines are never hit, they are handled by explode_mines
+        if let NoteType::Mine { .. } = bar.note_type {
+            continue;
+        }
 
-    for (entity, transform, bar) in query.iter() {
-        let pos = transform.translation.y;
+        // Negative when pressing early, positive when pressing late
+        let msval = ((secs - bar.hit_time) * 1000.) as f32;
 
-        // Check if bar is inside clicking threshold
-        if (TARGET_POSITION - THRESHOLD..=TARGET_POSITION + THRESHOLD).contains(&pos)
-            && (bar.position.key_just_pressed(&keyboard_input) || settings.autoplay_enabled)
-        {
-            // TODO this is stupid -- maybe set threshold to AUTOPLAY_THRESHOLD earlier if AUTOPLAY
-            // is enabled or something
-            if settings.autoplay_enabled {
-                if (TARGET_POSITION - AUTOPLAY_THRESHOLD..=TARGET_POSITION + AUTOPLAY_THRESHOLD)
-                    .contains(&pos)
-                {
-                    notes_in_threshold.push((entity, pos, bar));
-                }
-            } else {
-                // before despawning, check if first in lane in threshold?
-                // using lowest y pos for now -- might be bad?
-                notes_in_threshold.push((entity, pos, bar));
+        // Autoplay hits every note as soon as it reaches the judge line
+        let pressed = if settings.autoplay_enabled {
+            msval >= 0.
+        } else {
+            song_config
+                .layout
+                .key_just_pressed(bar.position, &keyboard_input)
+        };
+
+        if pressed && (-windows.bad..=windows.bad).contains(&msval) {
+            let oldest = lane_notes
+                .entry(bar.position)
+                .or_insert((entity, msval, bar));
+            if bar.hit_time < oldest.2.hit_time {
+                *oldest = (entity, msval, bar);
             }
+        } else if pressed && (-windows.poor..-windows.bad).contains(&msval) {
+            approaching.push(bar.position);
         }
 
-        // Despawn bar after they leave the screen
-        if pos <= 2. * TARGET_POSITION {
-            commands.entity(entity).despawn();
+        // Notes can't be hit once they're later than the bad window
+        if msval > windows.bad {
+            commands.entity(entity).despawn_recursive();
+            lane_keysounds.passed(bar.position, entity);
 
-            score.increase_fails();
+            score.add_miss();
         }
     }
 
-    // TODO need to account for notes in the same position (chords)
-    let min_note = notes_in_threshold.iter().min_by_key(|x| OrderedFloat(x.1));
+    // Each lane judges its own note, so chords and presses in several lanes at once all count
+    for (entity, msval, bar) in lane_notes.values() {
+        // Long notes stay around until they are released or reach their end
+        if let NoteType::Long { .. } = bar.note_type {
+            commands.entity(*entity).insert(HeldNote);
+        } else {
+            commands.entity(*entity).despawn();
+        }
+        lane_keysounds.hit(bar.position, *entity, bar.audio_source_id, secs);
+        song_config.play_sound(&audio, bar.audio_source_id);
+
+        let _points = score.increase_correct(*msval, windows);
+
+        // Only notes within the bad window get here
+        let judgement = Judgement::from_milliseconds(*msval, windows).unwrap_or(Judgement::Bad);
+        score.add_judgement(judgement);
+        score.add_timing(judgement, *msval);
+    }
 
-    // notes_in_threshold.sort_by_key(|x| OrderedFloat(x.1));
-    // let first_note = notes_in_threshold.first();
+    // Pressing a lane with nothing to hit still plays a keysound for that lane
+    if !settings.autoplay_enabled {
+        for position in song_config.layout.positions() {
+            if !song_config
+                .layout
+                .key_just_pressed(position, &keyboard_input)
+                || lane_notes.contains_key(&position)
+            {
+                continue;
+            }
 
-    let mut notes_to_play: Vec<Option<&(Entity, f32, &Bar)>> = Vec::new();
+            if approaching.contains(&position) {
+                score.add_empty_poor();
+            }
 
-    for note in notes_in_threshold.iter() {
-        if note.1 == min_note.expect("no min note").1 {
-            notes_to_play.push(Some(note));
+            let invisible = song_config.invisible_keysounds.keysound_at(position, secs);
+            if let Some(id) = lane_keysounds.empty_press_keysound(position, invisible) {
+                song_config.play_sound(&audio, id);
+            }
         }
     }
+}
 
-    for note in notes_to_play {
-        match note {
-            Some((entity, pos, bar)) => {
-                commands.entity(*entity).despawn();
-
-                // get audio handle
-                let audio_handle = song_config
-                    .audio_handles
-                    .get(&bar.audio_source_id)
-                    .expect("Audio source ID not found in map");
-
-                audio.play(audio_handle.clone()).with_volume(VOLUME);
-
-                let _points = score.increase_correct(TARGET_POSITION - pos);
-
-                let msval = calculate_milliseconds_from_target(
-                    *pos,
-                    TARGET_POSITION,
-                    settings.scroll_speed,
-                );
-
-                // using mostly IIDX timings for now
-                if msval <= 16.67 {
-                    score.pgreats += 1;
-                } else if msval <= 33.33 {
-                    score.greats += 1;
-                } else if msval <= 116.67 {
-                    score.goods += 1;
-                } else if msval <= 250.0 {
-                    score.bads += 1;
-                } else if msval <= 1000.0 {
-                    score.poors += 1;
-                } else {
-                    println!("MISS");
-                }
+/// Finishes long notes once their tail reaches the target, judging the release for CN and HCN
+fn hold_long_notes(
+    mut commands: Commands,
+    query: Query<(Entity, &Bar), With<HeldNote>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut score: ResMut<ScoreResource>,
+    settings: Res<UserSettings>,
+    time: Res<ControlledTime>,
+    song_config: Res<SongConfig>,
+) {
+    let secs = song_time(&time);
+    let windows = &settings.judge_windows;
+
+    for (entity, bar) in query.iter() {
+        let NoteType::Long { end_time } = bar.note_type else {
+            continue;
+        };
+        // Negative while the tail is still on its way to the target
+        let tail_msval = ((secs - end_time) * 1000.) as f32;
+        let holding = settings.autoplay_enabled
+            || song_config
+                .layout
+                .key_pressed(bar.position, &keyboard_input);
+
+        // Plain long notes only have to be held until the end, releasing early breaks them
+        if settings.ln_mode == LnMode::Ln {
+            if tail_msval >= 0. {
+                commands.entity(entity).despawn_recursive();
+            } else if !holding {
+                commands.entity(entity).despawn_recursive();
+
+                // The head was already judged, so the break is counted on its own
+                score.add_ln_break();
             }
-            None => {}
+            continue;
+        }
+
+        // Holding past the release window counts as a BAD release
+        if tail_msval > windows.bad {
+            commands.entity(entity).despawn_recursive();
+
+            score.add_tail_judgement(Judgement::Bad);
+            score.increase_fails();
+            continue;
+        }
+
+        if holding {
+            if settings.autoplay_enabled && tail_msval >= 0. {
+                commands.entity(entity).despawn_recursive();
+
+                score.add_tail_judgement(Judgement::PGreat);
+            } else if settings.ln_mode == LnMode::Hcn && tail_msval < 0. {
+                score.gauge.change(HCN_GAUGE_RATE * time.delta_seconds());
+            }
+            continue;
+        }
+
+        if tail_msval.abs() <= windows.bad {
+            commands.entity(entity).despawn_recursive();
+
+            let judgement =
+                Judgement::from_milliseconds(tail_msval, windows).unwrap_or(Judgement::Bad);
+            score.add_tail_judgement(judgement);
+            score.add_timing(judgement, tail_msval);
+        } else if settings.ln_mode == LnMode::Hcn {
+            // Hell charge notes can be pressed again, but drain the gauge until they are
+            score.gauge.change(-HCN_GAUGE_RATE * time.delta_seconds());
+        } else {
+            // Letting go of a charge note before its release window is a POOR, as in IIDX
+            commands.entity(entity).despawn_recursive();
+
+            score.add_tail_judgement(Judgement::Poor);
+            score.increase_fails();
+        }
+    }
+}
+
+/// Damages the gauge when a mine reaches the target while its lane key is held
+fn explode_mines(
+    mut commands: Commands,
+    query: Query<(Entity, &Bar)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut score: ResMut<ScoreResource>,
+    settings: Res<UserSettings>,
+    song_config: Res<SongConfig>,
+    time: Res<ControlledTime>,
+) {
+    let secs = song_time(&time);
+
+    for (entity, bar) in query.iter() {
+        let NoteType::Mine { damage } = bar.note_type else {
+            continue;
         };
+
+        if secs < bar.hit_time {
+            continue;
+        }
+
+        // Autoplay doesn't hold any keys, so it never sets off a mine
+        if !settings.autoplay_enabled
+            && song_config
+                .layout
+                .key_pressed(bar.position, &keyboard_input)
+        {
+            score.gauge.change(-damage);
+        }
+
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Chart time in seconds, which is at the judge line right now
+pub fn song_time(time: &ControlledTime) -> f64 {
+    time.seconds_since_startup() - SONG_START_DELAY
+}
+
+/// Y coordinate of a bar that reaches the judge line at `hit_time`, when the song is at `secs`
+fn bar_y(song_config: &SongConfig, hit_time: f64, secs: f64, settings: &UserSettings) -> f32 {
+    let scroll_distance = song_config.scroll_time(hit_time) - song_config.scroll_time(secs);
+    TARGET_POSITION + scroll_distance as f32 * settings.scroll_speed
+}
+
+/// Long notes scroll their whole body, so its height is the scroll time it covers
+fn body_length(
+    song_config: &SongConfig,
+    hit_time: f64,
+    note_type: NoteType,
+    settings: &UserSettings,
+) -> f32 {
+    match note_type {
+        NoteType::Long { end_time } => {
+            // Held notes are measured from the current time, which can be past the end
+            let scroll_time = song_config.scroll_time(end_time) - song_config.scroll_time(hit_time);
+            scroll_time.max(0.) as f32 * settings.scroll_speed
+        }
+        NoteType::Normal | NoteType::Mine { .. } => 0.,
     }
 }
 
-fn calculate_milliseconds_from_target(pos: f32, target_pos: f32, note_speed: f32) -> f32 {
-    let distance = (target_pos - pos).abs();
-    let time_in_seconds = distance / note_speed;
-    time_in_seconds * 1000.0
+/// Seconds it takes a bar to travel from its spawn position to the target
+fn travel_time(settings: &UserSettings) -> f64 {
+    ((SPAWN_POSITION - TARGET_POSITION) / settings.scroll_speed) as f64
 }
 
+/// Moves to results once every note has spawned and every bar on screen has been judged,
+/// so the last notes still count as hits, misses or long note releases
 fn show_results_on_finished(
     song_config: Res<SongConfig>,
+    bars: Query<(), With<Bar>>,
     mut next_state: ResMut<NextState<MyAppState>>,
 ) {
     // println!(
@@ -318,7 +581,7 @@ fn show_results_on_finished(
     // );
 
     // TODO for some reason we have leftover bgms, so just ignoring that for now
-    if song_config.notes.is_empty() {
+    if song_config.notes.is_empty() && bars.is_empty() {
         //&& song_config.bgms.is_empty() {
         next_state.set(MyAppState::Results);
         println!("Switching to Results state");
@@ -343,6 +606,7 @@ pub struct BarsPlugin<S: States> {
 impl<S: States> Plugin for BarsPlugin<S> {
     fn build(&self, app: &mut App) {
         app.init_resource::<BarMaterialResource>();
+        app.init_resource::<LaneKeysounds>();
         app.insert_resource(SpawnTimer(Timer::from_seconds(1.0, TimerMode::Repeating)));
         // app.add_systems(
         //     Startup,
@@ -355,6 +619,8 @@ impl<S: States> Plugin for BarsPlugin<S> {
                 spawn_bars,
                 move_bars,
                 despawn_bars,
+                hold_long_notes,
+                explode_mines,
                 play_bgms,
                 show_results_on_finished,
                 // debug_goto_results,
@@ -0,0 +1,292 @@
+use crate::chart_error::ChartLoadError;
+use crate::keysounds::KeysoundResolver;
+use crate::layouts::LaneLayout;
+use crate::new_bms_parser::read_utf8_chart;
+use crate::timing::TimingMap;
+use crate::types::{
+    AudioSlice, ChartMeta, NoteTime, NoteType, Positions, SongConfig, SoundId, BGM,
+};
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use serde_derive::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// The parts of a bmson chart we play, everything else in the file is ignored
+#[derive(Deserialize, Debug)]
+struct Bmson {
+    info: BmsonInfo,
+    #[serde(default)]
+    bpm_events: Vec<BpmEvent>,
+    #[serde(default)]
+    stop_events: Vec<StopEvent>,
+    #[serde(default)]
+    sound_channels: Vec<SoundChannel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BmsonInfo {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    subtitle: String,
+    #[serde(default)]
+    artist: String,
+    #[serde(default)]
+    genre: String,
+    /// Name of the difficulty, like "HYPER"
+    chart_name: Option<String>,
+    level: Option<u32>,
+    /// Judge rank in percent of the normal windows
+    judge_rank: Option<f64>,
+    total: Option<f64>,
+    #[serde(default = "default_mode_hint")]
+    mode_hint: String,
+    init_bpm: f64,
+    /// Pulses per beat
+    #[serde(default = "default_resolution")]
+    resolution: u64,
+}
+
+fn default_mode_hint() -> String {
+    "beat-7k".to_string()
+}
+
+fn default_resolution() -> u64 {
+    240
+}
+
+#[derive(Deserialize, Debug)]
+struct BpmEvent {
+    y: u64,
+    bpm: f64,
+}
+
+#[derive(Deserialize, Debug)]
+struct StopEvent {
+    y: u64,
+    /// Length of the stop in pulses
+    duration: u64,
+}
+
+/// A sound file and every note that plays part of it
+#[derive(Deserialize, Debug)]
+struct SoundChannel {
+    name: String,
+    notes: Vec<BmsonNote>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BmsonNote {
+    /// Lane of the note, or none for BGM
+    x: Option<u32>,
+    y: u64,
+    /// Length in pulses, long notes have one above 0
+    #[serde(default)]
+    l: u64,
+    /// Whether this note continues the sound from where the previous note in the channel left off
+    #[serde(default)]
+    c: bool,
+}
+
+/// Picks the lane layout for a chart's mode_hint
+fn layout_for(mode_hint: &str) -> LaneLayout {
+    match mode_hint {
+        "beat-5k" => LaneLayout::five_key(),
+        "beat-7k" => LaneLayout::seven_key(),
+        "beat-10k" | "beat-14k" => LaneLayout::double_play(),
+        "popn-5k" | "popn-9k" => LaneLayout::nine_key(),
+        _ => {
+            println!(
+                "Warning: unknown mode_hint {}, playing it as beat-7k",
+                mode_hint
+            );
+            LaneLayout::seven_key()
+        }
+    }
+}
+
+/// The lane for a note's x, which counts lanes from the left with the scratches last on each side
+fn lane_position(mode_hint: &str, x: u32) -> Option<Positions> {
+    use Positions::*;
+
+    let lanes: &[Positions] = if mode_hint.starts_with("popn") {
+        &[One, Two, Three, Four, Five, Six, Seven, Eight, Nine]
+    } else {
+        &[
+            One, Two, Three, Four, Five, Six, Seven, Scratch, P2One, P2Two, P2Three, P2Four,
+            P2Five, P2Six, P2Seven, P2Scratch,
+        ]
+    };
+
+    lanes.get((x as usize).checked_sub(1)?).copied()
+}
+
+pub fn load_config(
+    file_path: &str,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    let source = read_utf8_chart(file_path)?;
+    let bmson: Bmson =
+        serde_json::from_str(&source).map_err(|err| ChartLoadError::Parse(err.to_string()))?;
+    if bmson.info.init_bpm <= 0. {
+        return Err(ChartLoadError::MissingBpm);
+    }
+
+    let resolution = bmson.info.resolution.max(1) as f64;
+    // bmson doesn't require events in order, but the timing map does
+    let mut bpm_changes: Vec<(f64, f64)> = bmson
+        .bpm_events
+        .iter()
+        .map(|event| (event.y as f64 / resolution, event.bpm))
+        .collect();
+    let mut stops: Vec<(f64, f64)> = bmson
+        .stop_events
+        .iter()
+        .map(|event| {
+            (
+                event.y as f64 / resolution,
+                event.duration as f64 / resolution,
+            )
+        })
+        .collect();
+    bpm_changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let timing_map = TimingMap::from_beats(bmson.info.init_bpm, &bpm_changes, &stops);
+    let seconds_at = |y: u64| timing_map.seconds_at_beat(y as f64 / resolution);
+
+    let mode_hint = bmson.info.mode_hint.as_str();
+    let layout = layout_for(mode_hint);
+
+    let mut audio_handles_map: HashMap<SoundId, Handle<AudioSource>> = HashMap::new();
+    let mut audio_slices: HashMap<SoundId, AudioSlice> = HashMap::new();
+    let mut notetimes: Vec<NoteTime> = Vec::new();
+    let mut bgms_config_list: Vec<BGM> = Vec::new();
+
+    // Notes that can't be played, counted by lane and the reason they were skipped
+    let mut skipped_lanes: BTreeMap<(u32, &str), usize> = BTreeMap::new();
+    // Every note plays its own slice of its channel's sound, so each one gets a sound id
+    let mut sound_count = 0;
+
+    for channel in &bmson.sound_channels {
+        // Notes of a missing sound are still played, just silently
+        let audio_handle = keysounds.load(&channel.name);
+
+        let mut notes: Vec<&BmsonNote> = channel.notes.iter().collect();
+        notes.sort_by_key(|note| note.y);
+
+        // Pulse the sound was last started from the beginning
+        let mut sound_start = 0;
+
+        for (index, note) in notes.iter().enumerate() {
+            if !note.c {
+                sound_start = note.y;
+            }
+
+            let id = SoundId(sound_count);
+            sound_count += 1;
+            if let Some(audio_handle) = &audio_handle {
+                audio_handles_map.insert(id, audio_handle.clone());
+            }
+
+            // Channels are monophonic, so the next note in the channel cuts this one's sound off
+            let end = notes[index + 1..]
+                .iter()
+                .find(|next| next.y > note.y)
+                .map(|next| seconds_at(next.y) - seconds_at(sound_start));
+            audio_slices.insert(
+                id,
+                AudioSlice {
+                    start: seconds_at(note.y) - seconds_at(sound_start),
+                    end,
+                },
+            );
+
+            let spawn_time = seconds_at(note.y);
+
+            let x = note.x.unwrap_or(0);
+            if x == 0 {
+                bgms_config_list.push(BGM {
+                    spawn_time,
+                    audio_source_ids: vec![id],
+                });
+                continue;
+            }
+
+            let position = match lane_position(mode_hint, x) {
+                Some(position) if layout.lane(position).is_some() => position,
+                Some(_) => {
+                    *skipped_lanes
+                        .entry((x, "no lane in this layout"))
+                        .or_default() += 1;
+                    continue;
+                }
+                None => {
+                    *skipped_lanes.entry((x, "unknown lane")).or_default() += 1;
+                    continue;
+                }
+            };
+
+            let note_type = if note.l > 0 {
+                NoteType::Long {
+                    end_time: seconds_at(note.y + note.l),
+                }
+            } else {
+                NoteType::Normal
+            };
+
+            notetimes.push(NoteTime {
+                spawn_time,
+                position,
+                note_type,
+                audio_source_id: id,
+            });
+        }
+    }
+
+    for ((x, reason), count) in &skipped_lanes {
+        println!(
+            "Warning: skipped {} notes in lane {} ({})",
+            count, x, reason
+        );
+    }
+
+    notetimes.sort_by(|a, b| a.spawn_time.total_cmp(&b.spawn_time));
+    bgms_config_list.sort_by(|a, b| a.spawn_time.total_cmp(&b.spawn_time));
+
+    if notetimes.is_empty() {
+        return Err(ChartLoadError::EmptyChart);
+    }
+
+    let (min_bpm, max_bpm) = timing_map.bpm_range();
+    let mut meta = ChartMeta {
+        title: bmson.info.title.clone(),
+        subtitle: bmson.info.subtitle.clone(),
+        artist: bmson.info.artist.clone(),
+        genre: bmson.info.genre.clone(),
+        level: bmson.info.level,
+        difficulty: bmson
+            .info
+            .chart_name
+            .clone()
+            .filter(|name| !name.is_empty()),
+        total: bmson.info.total,
+        rank: bmson.info.judge_rank.map(|rank| format!("{}%", rank)),
+        min_bpm,
+        max_bpm,
+        encoding: "UTF-8",
+        ..default()
+    };
+    meta.count_notes(&notetimes);
+
+    Ok(SongConfig {
+        audio_slices,
+        ..SongConfig::new(
+            notetimes,
+            bgms_config_list,
+            timing_map.stop_times(),
+            layout,
+            meta,
+            audio_handles_map,
+        )
+    })
+}
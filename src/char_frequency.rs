@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// The tables in this file are derived from EUCKRFreq.tab and GB2312Freq.tab of Mozilla's
+// universal charset detector (extensions/universalchardet in mozilla-central). They are kept
+// in their own file so the MPL only covers this file and not the rest of the crate.
+
+/// The 2048 most frequent hangul syllables in Korean text, most frequent first. Taken from the
+/// EUC-KR distribution table of Mozilla's universal charset detector.
+pub const FREQUENT_HANGUL: &str =
+    "다이는하에을의로를한스서지가은있고리어기것용사수일트해시그들도정자면니인라으할나\
+    드보만여파되설게대터러제문아프부우적된버과모주위치크분와전디요메소상명경않작때음\
+    실신야장바데입같행구세내포동화템눅마른없당유호록개원성함습비합필번방커더결운든네\
+    간널단관션키래능저공각두패려토오램안중연력거령조법계미며워예또레생재선타진본알반\
+    렉통업컴었체준많히변접역식속등확처글약무블될매페현표브웨출름테목루형배추팅환발티\
+    쓰말했까특클언넷떤항임점값카참열영따코택받최직르몇줄링런란읽윈새질후립복료플옵찾\
+    렇권째좋태별물람겠편린퓨볼므근차종못초잘달절머존져외써검뉴됩금불판베색피집림았럼\
+    떻심누퍼웍순킷허련막얻케얼듈끝완론양꼴갖넣싶첫산곳송놓즉느올돌튼응격먼길답던백꾸\
+    콜앞였축혹쉘건너감쪽회움증룹캐술닉규맞책웹청류엔교릭엇온쉽급십께셋빠턴켜향텍난뒬\
+    렬품풀좀콘켓컬노걸쉬큐날붙쓸석뒤쳐충럽긴잡험년락벽살딩즈박효럭킨낸월뎀죠압숫킹몬\
+    강국킬넘닌뿐활롬픽겨담투왜창병솔짜삭군줍폴홈톨릴슬졌폰칙섹견낼햇울밀큰젝맨및떠롤\
+    밍펴애둘채헤혀멀벨슷억독취남랍범젼냐학황손왑센큼랙핑평량틸암익높툴략꼭냥즘쓴왔찬\
+    밖갈듯침옮칩씩논겁측랜텔뜻컨율친틀천핸벤맷잠눌액틴탑롭족갱욱똑엄릿삽폭층착징룰념\
+    삼씨빈례덱싱폼맵깨랫숙났망꺼밑죽객늘셔혼끔왼힘델낮꿀훨멈씬악찰둔웃띄엑획뀌끊훌깅\
+    륭렸칭렵찮셨셈깔봅귀얘님덧꿔눈닫맥믿셀쇄봐빨싸칠쿼탈짧럴북승탐묶잊첨꾼떨빼끼럿탕\
+    먹좌냅흔줘뭔빌펄총갑됨민픈슨뛰팩펜광룬멜믹걱곧뮬철텀괜넬극김뭐켰벌댑롯젯깊몰뜨렛\
+    듭짐흥닐춰뢰탭핀흐셸릅돈힌괴닙옴짓팁푼꽤헌납득놈썼쿠칼곤줌쇼희놀랩샘싼읍괄냈랑밝\
+    슈벡즐겼퀀갯셰뒷멤잭휘덮듣멋덜잃쥴끄쉐킵쳤쁜옛척틱캡횟갔골궁깐넥앙쨌꼬끌쟁염찍밉\
+    쁘섯뀐낭묻봤춘협딘숨쌍씀팸넓덤엉짝핵멍벗쩌털젠폐앨멘률쇠칸쯤팔균끗랄깁웠콤텐팀됐\
+    벳셧쌓윗튜팜깥앤촉퇴낌탄휴겟싫엽푸씌잇잎캔탁텝맡잉쪼겪곱낫봉냄늦렀풍낙넌녀퀘엘쉰\
+    육껴꽂낄뽑뿌굳뀔윤녹돕렴킥겹깝꿈덕홀뤄밤곽둡듀딸맛씁킴뉘룩멧섭옆홍훈땅랠맙묘섬팍\
+    닝뎁륨맘섞옥헷곡굵렌뷰슐욕찌딱잔굉놔돼땐뜬죄팝흑흠흡덩빔텟낳딪쏟펙힐묵앉랐봄옳췌\
+    뚱렐혜껏꼽닥듬떼맺핫긋꽉녕닛둬뜰춤톱꼼끈댓몽융겐굴긁눠닭덟딜뭘뮤벅츄츠쿨탠몫봇좁\
+    첩풉혔흉낡빛삐줬펌펑훑쫓팬흘깜껍꿉닮듦랬앰왕졸쩔겉굽맹멸뻔쭉켠탬혁훔휠농둥뛴밌붓\
+    컷샌썬잖쨋곰뜯렁뭉벼삶쎄얇헬낀뉜릇슴웁잦쩍춥턱튀헛겸돋뜹롱빙쁨쌨쭈촛캘쾌귄둠뜩띠\
+    밴삑샵옷쩝캠훼덴뚜밟붕뺀쉼앗짤탤텅펭꿨낯넉댈뎌렷몸밸숲싣싯엿왠짚챌챘칫큘펠홉흩뚫\
+    렝쌀웬윅젊쯔췄퀴틈펀힙걷귤꺽뀝뇨둑땜뛸뜀밥뱅븐슁썩욘윽탓톤펼풋긍껐낱냉녔뇌눔뒀뚝\
+    랭룡맣뭡붉빗뻣뼈샅쉴썽엠엣웅좇첵컸켈핏헉횡갠궈꼈꽁꾀뀜닿댄떴렘룻륙멎몹밗볍빡뻐뻗\
+    뿔샤샷섀썰얀얽쥬찐챈컫펨펫헐헝헨껄꼐꽃끙놨댁딕딴떡띌룸뭇뭣믄밋빚빽뺐샀셉싹쏘앳얄\
+    웜쥐찝촘츰쿡퀵큠큽팽흰갤곁굿깍깡깰꿋꿰끽넨녁눗늠돗돤딧떳뗄뜁띨뤘밈밭붐빅뺄뺑뾰뿅\
+    섣섰셕솟쑥앱옜웝젓줏쬐캣켤튤팎퍽헥혈혐훗갭갰겅겔궤넵늬닦닳댔돠딥딨뗬띈띕띤랗렙륜\
+    륵릉뭏믈벙뵈뷔빳뺏뺨쁠셜쒸씽앎얍엮옐윕윙읜잰즌쩡쭤찔찼챗챠촌춧켄켭콕콩콱탱텃툭팡\
+    픔휙갇갚걋걍겊겻깃꺄꺾껀껌꼰꽝꿜끓끕낍낚냇냑냔넋넙뉠뉨뉩뉼늰덥덫돔둣딛떽뗀뗌똔뚤\
+    뜸랴룔릍맬맴멕뮐믓밧볕뵐뵙붇빕빴빵뺍뻑뻤뻥뻬뿍쁩샐샛샹섦셥솜쇳숏슘쌌쌤쏙쏜쏴쑤씻\
+    앓앴엌엎옹웰읗잴잼잽쟈쟤졔좃좆좍죤죵쥔쥘짖짙짠짰쨈쩐쩜쩨쫌쫙쭝찡찹찻챔챕챙챦첸쳄\
+    츨칵컵켑콥콧콰쿰큅탯톡톰툇툼팃퍄푹퓟퓽핍햄햐헙훤휑휩흙힛갉갊갓갗갛갬갸갹갼걀걔걘\
+    걜걺겄겆겋겜겝겡겯곈곌곕곗곪곬곯곶괆괌괍괏괘괠괩괬괭괵괸괼굄굅굇굔굘굡굣굶굻굼궂\
+    궉궐궜궝궷귁귈귐귑귓귿긔긱긷긺깆깎깖깟깠깩깬깸깹깻깼깽꺅꺌껑껙껜껨껫껭껸껼꼇꼍꼲\
+    꼿꽈꽐꽜꽥꽹꾄꾈꾐꾑꾕꾜꾹꿇꿍꿎꿩꿱꿴꿸뀀뀁뀄뀨끅끎낏낑낟낢낵냘냠넒넛넜넝넴넸넹\
+    녈녑녘녜녠놂놉놋놘놜뇐뇔뇜뇝뇟뇩뇬뇰뇹뇻뇽눋눕눙눴눼뉵늄늅늉늑늙늚늡늣늪늴닒닢닯\
+    닷닸닺닻댐댕댜덖덛덞뎃뎄뎅뎐뎔뎠뎡뎨뎬돎돐돛돝돨됫됴뒈뒝뒨뒵뒹듄듐듕듸딤땀땁땃땄\
+    땋땍땔땝땟땠땡떪떫떰떱떵뗍뗏뗐뗑뗘똘똥똬똴뙈뙤뙨뚠뚬뛔뜅띔띰띱띳띵랏랒랖랸럇롄롑\
+    롓롸롼뢍뢨뢴뢸룀룁룃룅룐룝룟룽뤠뤼뤽륀륄륌륏륑륩륫릊릎맏맑맒맸먀먁먈먕멂멉멓멥멨\
+    멩멱몃몄몌몲뫄뫈뫘뫙뫼묀묄묍묏묑묜묠묩묫묽묾뭄뭅뭍뭬뮈뮌뮨뮴뮷믐밂밞뱀뱁뱃뱄뱉뱌\
+    뱍뱐뱝벋벎벚벧벰벱벴벵볏볐볘볜볶봔봬뵀뵉뵌뵘뵤뵨붊붑붚붜붤붰붸뷕뷘뷜뷩뷴뷸븀븃븅\
+    븍븜븝븟빎빤빪빰빱빻뺌뺘뺙뻘뻠뼁뼉뼘뼙뼛뼜뼝뽀뽁뽄뽈뽐뽕뾔뿜뿟뿡쀼쁑삔삘삠삡삣삥\
+    삯삳삵삿샙샜샥샨샬샴섄섈섐섕섟섧섶셌셍셤셩셴솅솎솖솝솥솨솩솬솰솽쇈쇌쇔쇗쇘쇤쇨쇰\
+    쇱쇽숀숄숌숍숑숟숩숭숯숱숴쉈쉑쉔쉠쉥쉭쉿슉슛슝슥슭싻쌈쌉쌔쌕쌘쌜쌥쌩썅썲썸썹쎈쎌\
+    쏀쏠쏢쏨쏩쏭쏵쏸쐈쐐쐤쐬쐰쐴쐼쐽쑈쑨쑬쑴쑵쑹쒀쒔쒜쒼쓩쓱쓺쓿씐씔씜씰씸씹앍앝앵얌\
+    얏얕얗얜얠얩얹얾엊엡엥엶엷엾옅옇옌옘옙옭옰옻왁왈왐왓왝왬왯왱왹욀욈욉욋욍욜욤욥욧\
+    욹욺웡웩웸웽윌윔윰윱윳윷읊읏읒읓읔읕읖읠읨읫읾잗잚잣잤잿쟀쟉쟌쟎쟐쟘쟝쟨쟬젖젤젬\
+    젭젱졀졈졉졍졺좔";
+
+/// The 2048 most frequent hanzi in simplified Chinese text, most frequent first. Taken from the
+/// GB2312 distribution table of Mozilla's universal charset detector.
+pub const FREQUENT_HANZI: &str =
+    "的一国在人了有中是年和大业不为发会工经上地市要个产这出行作生家以成到日民来我部对\
+    进多全建他公开们场展时理新方主企资实学报制政济用同于法高长现本月定化加动合品重关\
+    机分力自外者区能设后就等体下万元社过前面农也得与说之员而务利电文事可种总改三各好\
+    金第司其从平代当天水省提商十管内小技位目起海所立已通入量子问度北保心还科委都术使\
+    明着次将增基名向门应里美由规今题记点计去强两些表系办教正条最达特革收二期并程厂如\
+    道际及西口京华任调性导组东路活广意比投决交统党南安此领结营项情解议义山先车然价放\
+    世间因共院步物界集把持无但城相书村求治取原处府研质信四运县军件育局干队团又造形级\
+    标联专少费效据手施权江近深更认果格几看没职服台式益想数单样只被亿老受优常销志战流\
+    很接乡头给至难观指创证织论别五协变风批见究支那查张精每林转划准做需传争税构具百或\
+    才积势举必型易视快李参回引镇首推思完消值该走装众责备州供包副极整确知贸己环话反身\
+    选亚么带采王策真女谈严斯况色打德告仅它气料神率识劳境源青护列兴许户马港则节款拉直\
+    案股光较河花根布线土克再群医清速律她族历非感占续师何影功负验望财类货约艺售连纪按\
+    讯史示象养获石食抓富模始住赛客越闻央席坚份士热限米银息校均房周游千失八检足配存九\
+    命尔即防钱评复考依断范础油照段落访未额双让切须儿便空往你层低奖注黄英承远版维算破\
+    铁乐边初满病响药助致善突爱容香称购届余素请白宣健牌促培竞巴稳继紧字困刘旅声超随例\
+    担友号显却监材且春居适除红半买充陈火搞图阳六察试太什执片古七球修尽控讲排粮武预亲\
+    挥卖审措荣洲卫希店良属险曾围域令站苏龙念罗吨器汇康减习演普田班待星飞写矿轻扩言章\
+    汽靠毛终仍景置底福止离泽波兰核降训逐票菜座献钢眼损宁像苦印融独湖早予夫编换欧努著\
+    顾征升态套介送某斗状画留航派室临兵补宝略黑综云差纳密贫剧犯阿击遇岁阶烈督吃丰馆招\
+    害官树听庭另沙私针胜贷网愿托缺园假酒音巨既判输讨测读洋括筑欢刚庆久陆找楼激晚绝压\
+    故互签汉草木亩短绍迎吸警藏疗贵纷授登探索湾宏录申诉秀序顺死卡歌午孩桥喜川邓扬津温\
+    船库订练候退违否彩棉帮拿罪币角召灾妇杨奋绩虽煤免笔够永停奥鲜朝吴岛觉移尼急博贯拥\
+    束左细舞幅语俄奇般简拍脑债固威券追筹刻映繁伟甚饭右彻烟沿街血冲洪植誉刊玉厅救潮迅\
+    伍怎付倍顿述播励斤乎纸振旧障鼓艰呼吉男绿尚夏亏季松哈祖典韩遍夜轮板抗摄杂皮贡借幕\
+    罚伤岸扶乱曲脱践危澳童散味叶累谢孙邮雄兼微呢谁惠偿署择染答块徐鱼赞课盛延瑞怀堂驻\
+    零辆齐胡途封似润守毕坦母雨败朱污趋械纺租灵拓残含握跨衣储瓦蒙析鉴竟骨档秘禁赵宾异\
+    伊智钟键辉跃冷倒庄毒仪哪涉泛宗鹏归岗雷礼尤休泰疾肥珠叫牛宜抵挂寻父攻佳塞架符裁虑\
+    肉启丽露鲁秋昌估射册若宽厚盾硬末轨饮勤茶诗郑冠涨篇泥唱纯坡熟浙晓抢丝锦载笑勇杰患\
+    乌坐雪戏背塔翻沈遗聚渠哥享迹森辽衡掌牧附操赶览野盟殊仁错萨夺梅误词董潜卷矛腐亮冒\
+    盖旗井凡震峰坏倾距壮惊盘梁摆径忠冰峡丹避珍乘刑扎透迫箱莫跑穿祝乏厦渐软询折浪朋敢\
+    诚弱疑邀沉端床络疆缩脚甘贴勒荒唐静缓侵句尊塑肃怕耕痛援劣伙挑洗暴冬龄乔餐肯廉跟阵\
+    伐悉忘闭奔恢宋泉杯渡吗奉婚赴恩盐掉洁亡洛聘蔬混摩抽鸡剂胆麦谋雅废贺羊阔唯捐返隆穷\
+    辛猪帐饰郭颁灯绕诸伴顶祥谓恶番敏旦劲缴麻屋跳码鞋扣迈忙趣盈棋勃敬辑摊旺纠炼梦偏渔\
+    牙侨黎赔裕宫谷概稿柱弹殖秩凭拨幸洞伪沟姓遭涌陶迁诺拔畅忧胞丁蓄贝舍腾杀煌圆伦横薄\
+    畜毫豪弟呈佛邦您墨徽惯循蓝烧触陕拖伯盲宪净卢炭籍秦粉妻爆欣释玩俊欠蛋猛迪苗暂貌遵\
+    锡楚桂昆皇杜醒燃凤截铺液撤胶慢杭虚辞曼毅咨俗糖忽姐芳耗妈谊浦频阻允宅窗默胀弃倡灭\
+    甲症埃滨赏莱拒淡坛陵绘虎竹赢锋篮迷纽轿贩递娘圈挖炉替幼乃郊颇戴滑徒崇涛焦凝墙吧炎\
+    刀玻寿履圣昨酸朗媒桑铜仲亦诞揭纵漫愈辟赠旱奶泳枪骗虫池镜浓拆艾扫娱钻碍寒迟邻曹盗\
+    穆豆赚晨浩彭耳瓜扭脸燕摇寄仿炮晋泪欲饱壁锁刷柬诊磨捕寨滚膨孔添帝辖炸旨吁址驶抱嘉\
+    拜扰袋佩阴辈锅赖剩押怪浮枚栏毁柳恐敦孟旁仓岩伸岭耐懂捷璃溪暖纤汗疫巧旋侧冶陪鸣瓶\
+    纲挤旬舆喝陷缘稻饲滩隔慰朴隐灌拟偷闲赫恰慧蒋闹邹牵柴刺滞彰俱勘填尝贾搬淮奏荷滋覆\
+    役秒踏巩摸荡辅惜柜肖颗搏氏姑弄姜君舒兑宇割哲摘钦逃漠忆敌宿啊凌耀闯阅贪赤汪悲抑瓷\
+    冯厉粗菲琴堡斌掘稀衰驾雕牢氛驱妥悄郎巡臣羽灰癌颖姆漏袭贤鸟暗茂孤惩榜袁桌卓傅剑堆\
+    兆狠轰拳妹绒裂潘兄洽叹涵贿侯熊绪阁尾碑尖腿涂栽坝犹铸肩闪诱辩芬睡奠伏妙乙绸廷夕恒\
+    梯赁霞攀枝译描湘磁吕硕爸肝峻葡衷搭唤薪挺逝狗蔡宴蓬撞铝牲舰胁桃斜丧烂屏砖墓详逾函\
+    跌抚插戈凉啤脉滥赋柏堤腰泊寺尘蒂削仙踪冻汤睛艳荐劫框廊惑页拼堪携丈乳挪谱舶埔遥菌\
+    塘氧晶洒株颜虹岳胸忍甜匹瞩懈爷丛莲叙鸿逢抬嘴弘炒喷吊窝衔吹霸仔垦胎慎脏歧疏悠慕漂\
+    杆萍舟吐玲凯戒盼偶盆慨弊箭茅衫罐串辐腹钩碰昂酬晰姿彼锻飘嫁竣缝蹈悬紫浅缆喊昔驰湿\
+    剪侦坑姚魏扑挣焕皆狂泡骤堵膜禽锐芝帽擅沪晤婆埋劝碗玛顷鸭娃豫匆魂哭庞亭屡逼尺撒鹿\
+    讼弥坊碎缔霍壤萄铃稍丘肿烦苹庙雇汛孝辰吞汰怨酿耶咱欺丢琼棚披渴屈弗疲帕昭盒仰萧牺\
+    撑抛鼠纱翼兹骑糊契铭淘顽撰乒淑妆窑柔姻苍谨卿灿栋敲窃菊郁催眉邱揽鼎韦肤娜俏呀寸爬\
+    悟尿罢圭葬聪沃肠";
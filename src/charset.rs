@@ -0,0 +1,193 @@
+use crate::char_frequency::{FREQUENT_HANGUL, FREQUENT_HANZI};
+use crate::keysounds::KeysoundResolver;
+use encoding_rs::{Encoding, EUC_KR, GBK, SHIFT_JIS, UTF_8};
+use std::path::Path;
+
+/// Encodings charts are commonly saved in, most likely first so they win ties
+const CANDIDATES: [&Encoding; 4] = [UTF_8, SHIFT_JIS, EUC_KR, GBK];
+
+/// How much a #WAV file that exists on disk counts towards an encoding, against single characters
+const FOUND_WAV_SCORE: i64 = 10;
+
+/// How many of the most frequent hangul syllables count fully, the rest of the table counting half
+const VERY_FREQUENT_HANGUL: usize = 1024;
+
+/// Scores hangul by how frequent it is. Chinese text read as EUC-KR makes hangul too, but its
+/// syllables are spread evenly over KS X 1001 instead of leaning on the frequent ones.
+fn hangul_score(c: char) -> i64 {
+    match FREQUENT_HANGUL.chars().position(|hangul| hangul == c) {
+        Some(rank) if rank < VERY_FREQUENT_HANGUL => 2,
+        Some(_) => 1,
+        None => -1,
+    }
+}
+
+/// Whether a kanji is in JIS X 0208 level 1, the commonly used ones
+fn is_jis_level_1(c: char) -> bool {
+    let mut buffer = [0; 4];
+    let (bytes, _, unmappable) = SHIFT_JIS.encode(c.encode_utf8(&mut buffer));
+    !unmappable && matches!(bytes.first(), Some(0x88..=0x98))
+}
+
+/// Scores a CJK ideograph by how common it is in the language of the encoding
+fn hanzi_score(c: char, encoding: &'static Encoding) -> i64 {
+    if encoding == EUC_KR {
+        // Korean titles rarely use hanja, while Chinese text read as EUC-KR often turns into it
+        -1
+    } else if encoding == SHIFT_JIS {
+        i64::from(is_jis_level_1(c))
+    } else if FREQUENT_HANZI.contains(c) {
+        2
+    } else {
+        0
+    }
+}
+
+/// How likely a character is to show up in chart text that was decoded with `encoding`
+fn char_score(c: char, encoding: &'static Encoding) -> i64 {
+    match c {
+        // Hiragana and katakana rarely come out of the wrong encoding
+        '\u{3040}'..='\u{30FF}' => 1,
+        // Half-width katakana is what Korean and Chinese text usually turns into as Shift-JIS
+        '\u{FF61}'..='\u{FF9F}' => -1,
+        // Compatibility ideographs and private use characters are almost always mojibake
+        '\u{F900}'..='\u{FAFF}' | '\u{E000}'..='\u{F8FF}' => -5,
+        c if c.is_control() && !c.is_whitespace() => -5,
+        '\u{AC00}'..='\u{D7A3}' => hangul_score(c),
+        '\u{4E00}'..='\u{9FFF}' => hanzi_score(c, encoding),
+        _ => 0,
+    }
+}
+
+/// File names of the #WAVxx commands in a chart
+fn wav_files(source: &str) -> impl Iterator<Item = &str> {
+    source.lines().filter_map(|line| {
+        let line = line.trim();
+        let command = line.get(..4)?;
+
+        if !command.eq_ignore_ascii_case("#WAV") {
+            return None;
+        }
+
+        // Skip the two character object id
+        line.get(6..).map(str::trim)
+    })
+}
+
+/// Folder set with #PATH_WAV, which the #WAV file names are relative to
+fn path_wav(source: &str) -> Option<&Path> {
+    source.lines().find_map(|line| {
+        let line = line.trim();
+        let command = line.get(..9)?;
+
+        if !command.eq_ignore_ascii_case("#PATH_WAV") {
+            return None;
+        }
+
+        Some(Path::new(line.get(9..)?.trim()))
+    })
+}
+
+/// Decodes chart text, picking the candidate encoding whose text looks most plausible and whose
+/// #WAV file names can be found on disk. Returns None if no candidate decodes without errors.
+pub fn decode_chart(
+    data: &[u8],
+    keysounds: &mut KeysoundResolver,
+) -> Option<(String, &'static Encoding)> {
+    // A byte order mark settles it
+    if let Some((encoding, bom_length)) = Encoding::for_bom(data) {
+        let source =
+            encoding.decode_without_bom_handling_and_without_replacement(&data[bom_length..])?;
+        return Some((source.into_owned(), encoding));
+    }
+
+    let mut best: Option<(i64, String, &'static Encoding)> = None;
+
+    for encoding in CANDIDATES {
+        let Some(source) = encoding.decode_without_bom_handling_and_without_replacement(data)
+        else {
+            continue;
+        };
+
+        let text_score: i64 = source.chars().map(|c| char_score(c, encoding)).sum();
+        // #PATH_WAV can only be read once the text is decoded, so it's set for each candidate
+        keysounds.set_sound_path(path_wav(&source));
+        let found_wavs = wav_files(&source)
+            .filter(|wav_file| keysounds.resolve(wav_file).is_some())
+            .count() as i64;
+        let score = text_score + found_wavs * FOUND_WAV_SCORE;
+
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _, _)| score > *best_score)
+        {
+            best = Some((score, source.into_owned(), encoding));
+        }
+    }
+
+    best.map(|(_, source, encoding)| (source, encoding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a chart with `title` in `encoding` and decodes it again, returning the encoding picked
+    fn detect(title: &str, encoding: &'static Encoding) -> &'static str {
+        let source = format!("#TITLE {}\n#ARTIST test\n#BPM 150\n", title);
+        let (data, _, unmappable) = encoding.encode(&source);
+        assert!(
+            !unmappable,
+            "{} can't be encoded in {}",
+            title,
+            encoding.name()
+        );
+
+        let mut keysounds = KeysoundResolver::without_loading("songs/missing/chart.bms", &[]);
+        let (decoded, picked) = decode_chart(&data, &mut keysounds).expect("could not decode");
+        assert_eq!(picked == encoding, decoded == source);
+
+        picked.name()
+    }
+
+    #[test]
+    fn detects_gbk() {
+        for title in [
+            "青鸟",
+            "晴天",
+            "稻香",
+            "告白气球",
+            "平凡之路",
+            "海阔天空",
+            "光年之外",
+        ] {
+            assert_eq!(detect(title, GBK), GBK.name(), "{}", title);
+        }
+    }
+
+    #[test]
+    fn detects_euc_kr() {
+        for title in [
+            "푸른 숲",
+            "봄날",
+            "밤편지",
+            "그대에게",
+            "바람이 분다",
+            "소나기",
+        ] {
+            assert_eq!(detect(title, EUC_KR), EUC_KR.name(), "{}", title);
+        }
+    }
+
+    #[test]
+    fn detects_shift_jis() {
+        for title in ["千本桜", "夜に駆ける", "紅蓮華", "天体観測", "東京"] {
+            assert_eq!(detect(title, SHIFT_JIS), SHIFT_JIS.name(), "{}", title);
+        }
+    }
+
+    #[test]
+    fn detects_utf_8() {
+        assert_eq!(detect("恋爱循环 사랑 夜に駆ける", UTF_8), UTF_8.name());
+    }
+}
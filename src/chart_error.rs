@@ -0,0 +1,46 @@
+use bms_rs::lex::command::ObjId;
+use std::fmt;
+
+/// Why a chart couldn't be loaded
+#[derive(Debug)]
+pub enum ChartLoadError {
+    /// The chart file couldn't be read
+    Io(std::io::Error),
+    /// The chart isn't in any text encoding we can read
+    Encoding,
+    /// The chart's commands couldn't be split into tokens
+    Lex(String),
+    /// The chart's contents don't make a valid chart
+    Parse(String),
+    /// The chart never sets a positive starting BPM, so its notes can't be timed
+    MissingBpm,
+    /// The chart has no notes we can play
+    EmptyChart,
+    /// A note or BGM plays an object that has no #WAV definition
+    UndefinedWav(ObjId),
+    /// The chart is for a game mode that can't be played here, like osu!standard
+    UnsupportedMode(String),
+}
+
+impl fmt::Display for ChartLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the chart file: {}", err),
+            Self::Encoding => write!(f, "could not decode the chart text"),
+            Self::Lex(err) => write!(f, "could not read the chart commands: {}", err),
+            Self::Parse(err) => write!(f, "could not parse the chart: {}", err),
+            Self::MissingBpm => write!(f, "the chart has no BPM"),
+            Self::EmptyChart => write!(f, "the chart has no playable notes"),
+            Self::UndefinedWav(id) => write!(f, "object {:?} has no #WAV definition", id),
+            Self::UnsupportedMode(mode) => write!(f, "the chart is for {}", mode),
+        }
+    }
+}
+
+impl std::error::Error for ChartLoadError {}
+
+impl From<std::io::Error> for ChartLoadError {
+    fn from(err: std::io::Error) -> Self {
+        ChartLoadError::Io(err)
+    }
+}
@@ -0,0 +1,66 @@
+use bms_rs::parse::rng::Rng;
+use std::ops::RangeInclusive;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seeded random number generator for #RANDOM, which remembers every value it picked
+pub struct ChartRng {
+    seed: u64,
+    state: u64,
+    /// Value to pick instead of a random one, for practicing a specific branch
+    branch_override: Option<u32>,
+    picked: Vec<u32>,
+}
+
+impl ChartRng {
+    pub fn new(seed: u64, branch_override: Option<u32>) -> Self {
+        ChartRng {
+            seed,
+            state: seed,
+            branch_override,
+            picked: Vec::new(),
+        }
+    }
+
+    /// Seeds the generator from the current time
+    pub fn from_time(branch_override: Option<u32>) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+
+        Self::new(seed, branch_override)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Values picked for each #RANDOM, in the order they appear in the chart
+    pub fn picked(&self) -> &[u32] {
+        &self.picked
+    }
+
+    /// splitmix64, good enough for picking branches and easy to reproduce from a seed
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+// bms-rs takes the generator by value, so it borrows ours to let us read the picks afterwards
+impl Rng for &mut ChartRng {
+    fn gen(&mut self, range: RangeInclusive<u32>) -> u32 {
+        let value = match self.branch_override {
+            Some(value) if range.contains(&value) => value,
+            _ => {
+                let span = (*range.end() as u64).saturating_sub(*range.start() as u64) + 1;
+                *range.start() + (self.next_u64() % span) as u32
+            }
+        };
+
+        self.picked.push(value);
+        value
+    }
+}
@@ -9,11 +9,8 @@ pub const SPAWN_POSITION: f32 = 300.;
 /// Y coordinate value where the bars should be clicked
 pub const TARGET_POSITION: f32 = -300.;
 
-/// Margin of error for clicking a note
-pub const THRESHOLD: f32 = 100.;
-
-/// This should probably be temporary maybe idk
-pub const AUTOPLAY_THRESHOLD: f32 = 1.;
+/// Seconds after entering the game at which the chart's time 0 reaches the judge line
+pub const SONG_START_DELAY: f64 = 3.75;
 
 /// Total distance traveled by a note, from spawn to target
 pub const DISTANCE: f32 = TARGET_POSITION - SPAWN_POSITION;
@@ -24,6 +21,18 @@ pub const VOLUME: f64 = 0.3;
 /// Temporary autoplay option
 pub const AUTOPLAY_ENABLED: bool = true;
 
+/// Gauge percentage at the start of a song
+pub const GAUGE_START: f32 = 20.;
+
+/// Lowest the gauge can drop to
+pub const GAUGE_MIN: f32 = 2.;
+
+/// Gauge percentage lost when a key is pressed early on an approaching note, an empty POOR
+pub const EMPTY_POOR_GAUGE_DAMAGE: f32 = 2.;
+
+/// Gauge percentage gained per second while holding a hell charge note, and lost while not
+pub const HCN_GAUGE_RATE: f32 = 5.;
+
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MyAppState {
     MainMenu,
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::read_dir;
+use std::path::{Component, Path, PathBuf};
+
+/// Finds and loads the sound files a chart refers to.
+///
+/// Charts are often authored on Windows, so names are matched without case, and a sound that's
+/// missing under its own extension is looked for under each of the configured ones instead.
+pub struct KeysoundResolver<'a> {
+    /// None when only reading a chart, so nothing gets loaded
+    asset_server: Option<&'a AssetServer>,
+    /// Folder the chart is in, relative to `assets/`
+    chart_dir: PathBuf,
+    /// Folder sound names are relative to, itself relative to `assets/` unless it's absolute
+    sound_dir: PathBuf,
+    /// Extensions to try, in order, when a sound isn't found under its own
+    extensions: Vec<String>,
+    /// Files in each folder searched so far, keyed by lower case name
+    listings: HashMap<PathBuf, HashMap<String, String>>,
+    missing: BTreeSet<String>,
+}
+
+/// Lists the files in a folder under `assets/`, keyed by lower case name
+fn list_dir(dir: &Path) -> HashMap<String, String> {
+    let Ok(entries) = read_dir(Path::new("assets").join(dir)) else {
+        return HashMap::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .map(|name| (name.to_lowercase(), name))
+        .collect()
+}
+
+impl<'a> KeysoundResolver<'a> {
+    /// Creates a resolver for the sounds of the chart at `file_path`, which live next to it
+    pub fn new(file_path: &str, extensions: &[String], asset_server: &'a AssetServer) -> Self {
+        KeysoundResolver {
+            asset_server: Some(asset_server),
+            ..Self::without_loading(file_path, extensions)
+        }
+    }
+
+    /// Creates a resolver that finds sounds but never loads them, for reading chart metadata
+    pub fn without_loading(file_path: &str, extensions: &[String]) -> Self {
+        let chart_dir = Path::new(file_path)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+
+        KeysoundResolver {
+            asset_server: None,
+            sound_dir: chart_dir.clone(),
+            chart_dir,
+            extensions: extensions.to_vec(),
+            listings: HashMap::new(),
+            missing: BTreeSet::new(),
+        }
+    }
+
+    /// Looks for sounds in `path` (#PATH_WAV) instead of next to the chart, or next to the chart
+    /// again for None. A relative path is taken from the chart's folder, an absolute one is used
+    /// as is.
+    pub fn set_sound_path(&mut self, path: Option<&Path>) {
+        self.sound_dir = match path {
+            Some(path) => self
+                .chart_dir
+                .join(path.to_string_lossy().replace('\\', "/")),
+            None => self.chart_dir.clone(),
+        };
+    }
+
+    /// Files in a folder under `assets/`, listed once and then cached
+    fn listing(&mut self, dir: &Path) -> &HashMap<String, String> {
+        self.listings
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| list_dir(dir))
+    }
+
+    /// Matches each folder of `dir` against the ones on disk without case. Folders that can't be
+    /// found are kept as they are.
+    fn find_dir(&mut self, dir: &Path) -> PathBuf {
+        let mut found = PathBuf::new();
+
+        for component in dir.components() {
+            let Component::Normal(name) = component else {
+                found.push(component);
+                continue;
+            };
+
+            let name = name.to_string_lossy();
+            let actual = self
+                .listing(&found)
+                .get(&name.to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| name.into_owned());
+            found.push(actual);
+        }
+
+        found
+    }
+
+    /// Path under `assets/` of a sound file, if it can be found in any of the extensions
+    pub fn resolve(&mut self, sound_file: impl AsRef<Path>) -> Option<PathBuf> {
+        // Windows style separators are common in charts
+        let sound_file = sound_file.as_ref().to_string_lossy().replace('\\', "/");
+        let path = self.sound_dir.join(sound_file);
+        let dir = self.find_dir(path.parent()?);
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        let stem = path.file_stem()?.to_str()?.to_lowercase();
+
+        let listing = self
+            .listings
+            .entry(dir.clone())
+            .or_insert_with(|| list_dir(&dir));
+
+        let found = std::iter::once(file_name)
+            .chain(
+                self.extensions
+                    .iter()
+                    .map(|extension| format!("{}.{}", stem, extension.to_lowercase())),
+            )
+            .find_map(|name| listing.get(&name))?;
+
+        Some(dir.join(found))
+    }
+
+    /// Loads a sound file, or remembers it as missing if it can't be found
+    pub fn load(&mut self, sound_file: impl AsRef<Path>) -> Option<Handle<AudioSource>> {
+        match self.resolve(&sound_file) {
+            Some(path) => self
+                .asset_server
+                .map(|asset_server| asset_server.load(path)),
+            None => {
+                self.missing
+                    .insert(sound_file.as_ref().to_string_lossy().into_owned());
+                None
+            }
+        }
+    }
+
+    /// Prints every sound that couldn't be found, after all of a chart's sounds were loaded
+    pub fn report_missing(&self) {
+        if self.missing.is_empty() {
+            return;
+        }
+
+        let missing: Vec<&str> = self.missing.iter().map(String::as_str).collect();
+        println!(
+            "Warning: {} sounds could not be found: {}",
+            missing.len(),
+            missing.join(", ")
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use crate::types::Positions;
+use bevy::input::keyboard::KeyCode;
+use bevy::prelude::*;
+use bms_rs::lex::command::{Key, PlayerMode};
+use std::collections::{HashMap, HashSet};
+
+/// Which of the note textures a lane is drawn with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LaneTexture {
+    White,
+    Blue,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lane {
+    pub position: Positions,
+    pub x: f32,
+    pub texture: LaneTexture,
+    pub keys: Vec<KeyCode>,
+}
+
+/// Lanes a chart is played on, and which note channel goes to which lane
+#[derive(Clone, Debug)]
+pub struct LaneLayout {
+    pub name: &'static str,
+    pub lanes: Vec<Lane>,
+    /// Width of a lane in pixels
+    pub lane_width: f32,
+    /// Lane for each note channel, keyed by (is player 1 side, key)
+    pub channel_map: HashMap<(bool, Key), Positions>,
+}
+
+/// Lays out a row of lanes from left to right, starting at `start_x`
+fn lane_row(
+    start_x: f32,
+    lane_width: f32,
+    lanes: &[(Positions, LaneTexture, &[KeyCode])],
+) -> Vec<Lane> {
+    lanes
+        .iter()
+        .enumerate()
+        .map(|(i, (position, texture, keys))| Lane {
+            position: *position,
+            x: start_x + i as f32 * lane_width,
+            texture: *texture,
+            keys: keys.to_vec(),
+        })
+        .collect()
+}
+
+/// Channels 11-19 as they are used by 5 and 7 key BMS
+const BMS_1P_CHANNELS: [(Key, Positions); 8] = [
+    (Key::Key1, Positions::One),
+    (Key::Key2, Positions::Two),
+    (Key::Key3, Positions::Three),
+    (Key::Key4, Positions::Four),
+    (Key::Key5, Positions::Five),
+    (Key::Key6, Positions::Six),
+    (Key::Key7, Positions::Seven),
+    (Key::Scratch, Positions::Scratch),
+];
+
+/// Channels 21-29 as they are used by DP BMS
+const BMS_2P_CHANNELS: [(Key, Positions); 8] = [
+    (Key::Key1, Positions::P2One),
+    (Key::Key2, Positions::P2Two),
+    (Key::Key3, Positions::P2Three),
+    (Key::Key4, Positions::P2Four),
+    (Key::Key5, Positions::P2Five),
+    (Key::Key6, Positions::P2Six),
+    (Key::Key7, Positions::P2Seven),
+    (Key::Scratch, Positions::P2Scratch),
+];
+
+/// Channels 11-15 and 22-25 as they are used by PMS for its 9 buttons
+const PMS_CHANNELS: [((bool, Key), Positions); 9] = [
+    ((true, Key::Key1), Positions::One),
+    ((true, Key::Key2), Positions::Two),
+    ((true, Key::Key3), Positions::Three),
+    ((true, Key::Key4), Positions::Four),
+    ((true, Key::Key5), Positions::Five),
+    ((false, Key::Key2), Positions::Six),
+    ((false, Key::Key3), Positions::Seven),
+    ((false, Key::Key4), Positions::Eight),
+    ((false, Key::Key5), Positions::Nine),
+];
+
+impl LaneLayout {
+    /// Builds the channel map from the channels of the lanes in `lanes`
+    fn new(
+        name: &'static str,
+        lane_width: f32,
+        lanes: Vec<Lane>,
+        channels: &[((bool, Key), Positions)],
+    ) -> Self {
+        let channel_map = channels
+            .iter()
+            .filter(|(_, position)| lanes.iter().any(|lane| lane.position == *position))
+            .copied()
+            .collect();
+
+        LaneLayout {
+            name,
+            lanes,
+            lane_width,
+            channel_map,
+        }
+    }
+
+    fn bms_channels() -> Vec<((bool, Key), Positions)> {
+        let p1 = BMS_1P_CHANNELS.map(|(key, position)| ((true, key), position));
+        let p2 = BMS_2P_CHANNELS.map(|(key, position)| ((false, key), position));
+
+        p1.into_iter().chain(p2).collect()
+    }
+
+    pub fn five_key() -> Self {
+        use LaneTexture::*;
+
+        let lanes = lane_row(
+            -300.,
+            100.,
+            &[
+                (Positions::Scratch, White, &[KeyCode::ShiftLeft]),
+                (Positions::One, Blue, &[KeyCode::KeyD]),
+                (Positions::Two, White, &[KeyCode::KeyF]),
+                (Positions::Three, Blue, &[KeyCode::Space]),
+                (Positions::Four, White, &[KeyCode::KeyJ]),
+                (Positions::Five, Blue, &[KeyCode::KeyK]),
+            ],
+        );
+
+        Self::new("5K", 100., lanes, &Self::bms_channels())
+    }
+
+    pub fn seven_key() -> Self {
+        use LaneTexture::*;
+
+        let lanes = lane_row(
+            -400.,
+            100.,
+            &[
+                (
+                    Positions::Scratch,
+                    White,
+                    &[KeyCode::ShiftLeft, KeyCode::Semicolon],
+                ),
+                (Positions::One, Blue, &[KeyCode::KeyA]),
+                (Positions::Two, White, &[KeyCode::KeyS]),
+                (Positions::Three, Blue, &[KeyCode::KeyD]),
+                (Positions::Four, White, &[KeyCode::Space]),
+                (Positions::Five, Blue, &[KeyCode::KeyJ]),
+                (Positions::Six, White, &[KeyCode::KeyK]),
+                (Positions::Seven, Blue, &[KeyCode::KeyL]),
+            ],
+        );
+
+        Self::new("7K", 100., lanes, &Self::bms_channels())
+    }
+
+    /// Pop'n style 9 buttons, which PMS charts place on channels 11-15 and 22-25
+    pub fn nine_key() -> Self {
+        use LaneTexture::*;
+
+        let lanes = lane_row(
+            -400.,
+            100.,
+            &[
+                (Positions::One, White, &[KeyCode::KeyA]),
+                (Positions::Two, Blue, &[KeyCode::KeyS]),
+                (Positions::Three, White, &[KeyCode::KeyD]),
+                (Positions::Four, Blue, &[KeyCode::KeyF]),
+                (Positions::Five, White, &[KeyCode::Space]),
+                (Positions::Six, Blue, &[KeyCode::KeyJ]),
+                (Positions::Seven, White, &[KeyCode::KeyK]),
+                (Positions::Eight, Blue, &[KeyCode::KeyL]),
+                (Positions::Nine, White, &[KeyCode::Semicolon]),
+            ],
+        );
+
+        Self::new("9K", 100., lanes, &PMS_CHANNELS)
+    }
+
+    /// 14 keys and 2 scratches, with the 1P side moved to the left half of the keyboard
+    pub fn double_play() -> Self {
+        use LaneTexture::*;
+
+        let mut lanes = lane_row(
+            -590.,
+            70.,
+            &[
+                (Positions::Scratch, White, &[KeyCode::ShiftLeft]),
+                (Positions::One, Blue, &[KeyCode::KeyZ]),
+                (Positions::Two, White, &[KeyCode::KeyS]),
+                (Positions::Three, Blue, &[KeyCode::KeyX]),
+                (Positions::Four, White, &[KeyCode::KeyD]),
+                (Positions::Five, Blue, &[KeyCode::KeyC]),
+                (Positions::Six, White, &[KeyCode::KeyF]),
+                (Positions::Seven, Blue, &[KeyCode::KeyV]),
+            ],
+        );
+        lanes.extend(lane_row(
+            100.,
+            70.,
+            &[
+                (Positions::P2One, Blue, &[KeyCode::KeyM]),
+                (Positions::P2Two, White, &[KeyCode::KeyK]),
+                (Positions::P2Three, Blue, &[KeyCode::Comma]),
+                (Positions::P2Four, White, &[KeyCode::KeyL]),
+                (Positions::P2Five, Blue, &[KeyCode::Period]),
+                (Positions::P2Six, White, &[KeyCode::Semicolon]),
+                (Positions::P2Seven, Blue, &[KeyCode::Slash]),
+                (Positions::P2Scratch, White, &[KeyCode::ShiftRight]),
+            ],
+        ));
+
+        Self::new("DP", 70., lanes, &Self::bms_channels())
+    }
+
+    /// StepMania's dance-single panels: left, down, up and right
+    pub fn four_panel() -> Self {
+        use LaneTexture::*;
+
+        let lanes = lane_row(
+            -200.,
+            100.,
+            &[
+                (Positions::One, White, &[KeyCode::KeyD]),
+                (Positions::Two, Blue, &[KeyCode::KeyF]),
+                (Positions::Three, Blue, &[KeyCode::KeyJ]),
+                (Positions::Four, White, &[KeyCode::KeyK]),
+            ],
+        );
+
+        Self::new("4 panel", 100., lanes, &[])
+    }
+
+    /// StepMania's pump-single panels: down left, up left, center, up right and down right
+    pub fn five_panel() -> Self {
+        use LaneTexture::*;
+
+        let lanes = lane_row(
+            -250.,
+            100.,
+            &[
+                (Positions::One, Blue, &[KeyCode::KeyD]),
+                (Positions::Two, White, &[KeyCode::KeyF]),
+                (Positions::Three, Blue, &[KeyCode::Space]),
+                (Positions::Four, White, &[KeyCode::KeyJ]),
+                (Positions::Five, Blue, &[KeyCode::KeyK]),
+            ],
+        );
+
+        Self::new("5 panel", 100., lanes, &[])
+    }
+
+    /// Picks the layout for a chart from its file extension, #PLAYER and the channels its visible
+    /// and long notes use
+    pub fn detect(
+        extension: &str,
+        player: Option<&PlayerMode>,
+        channels: &HashSet<(bool, Key)>,
+    ) -> Self {
+        // PMS charts saved as .bms or .bme only show it through their channels, which stay within
+        // 11-15 and 22-25
+        let uses_pms_channels = channels.iter().any(|(is_player1, _)| !is_player1)
+            && channels.iter().all(|channel| {
+                PMS_CHANNELS
+                    .iter()
+                    .any(|(pms_channel, _)| pms_channel == channel)
+            });
+        if extension.eq_ignore_ascii_case("pms") || uses_pms_channels {
+            return Self::nine_key();
+        }
+
+        // #PLAYER 3 marks a DP chart, but plenty of them only show it through their 2P notes.
+        // An explicit #PLAYER 1 is trusted over those.
+        let is_double_play = match player {
+            Some(PlayerMode::Double) => true,
+            Some(PlayerMode::Single) => false,
+            _ => channels.iter().any(|(is_player1, _)| !is_player1),
+        };
+        if is_double_play {
+            return Self::double_play();
+        }
+
+        let uses_seven_keys = channels
+            .iter()
+            .any(|(_, key)| matches!(key, Key::Key6 | Key::Key7));
+        if uses_seven_keys || extension.eq_ignore_ascii_case("bme") {
+            Self::seven_key()
+        } else {
+            Self::five_key()
+        }
+    }
+
+    pub fn lane(&self, position: Positions) -> Option<&Lane> {
+        self.lanes.iter().find(|lane| lane.position == position)
+    }
+
+    pub fn lane_count(&self) -> usize {
+        self.lanes.len()
+    }
+
+    /// Every lane's position, from left to right
+    pub fn positions(&self) -> impl Iterator<Item = Positions> + '_ {
+        self.lanes.iter().map(|lane| lane.position)
+    }
+
+    /// Returns the correct x coordinate for a bar in this lane
+    pub fn x(&self, position: Positions) -> f32 {
+        self.lane(position).map_or(0., |lane| lane.x)
+    }
+
+    pub fn texture(&self, position: Positions) -> LaneTexture {
+        self.lane(position)
+            .map_or(LaneTexture::White, |lane| lane.texture)
+    }
+
+    /// Checks if a key bound to this lane has been pressed
+    pub fn key_just_pressed(&self, position: Positions, input: &ButtonInput<KeyCode>) -> bool {
+        self.lane(position)
+            .is_some_and(|lane| lane.keys.iter().any(|code| input.just_pressed(*code)))
+    }
+
+    /// Checks if a key bound to this lane is being pressed
+    pub fn key_pressed(&self, position: Positions, input: &ButtonInput<KeyCode>) -> bool {
+        self.lane(position)
+            .is_some_and(|lane| lane.keys.iter().any(|code| input.pressed(*code)))
+    }
+}
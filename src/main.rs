@@ -13,12 +13,22 @@ const BACKGROUND_COLOR: Color = Color::rgb(0., 0., 0.);
 mod bars;
 use bars::BarsPlugin;
 mod bms_parser;
+mod bmson;
+mod char_frequency;
+mod charset;
+mod chart_error;
+mod chart_random;
 mod consts;
+mod keysounds;
+mod layouts;
 mod menu;
 mod new_bms_parser;
+mod osu;
 mod results;
 mod score;
+mod stepmania;
 mod time;
+mod timing;
 mod types;
 mod ui;
 mod user_settings;
@@ -69,10 +79,14 @@ fn setup(
     mut commands: Commands,
     // window_query: Query<&Window, With<PrimaryWindow>>,
     asset_server: Res<AssetServer>,
+    settings: Res<UserSettings>,
 ) {
-    let config = types::load_config("songs/[Cres.]endtime/end_time_n.bms", &asset_server);
+    let song = "songs/[Cres.]endtime/end_time_n.bms";
 
     // Camera
     commands.spawn(Camera2dBundle::default());
-    commands.insert_resource(config);
+    match types::load_config(song, &asset_server, &settings) {
+        Ok(config) => commands.insert_resource(config),
+        Err(err) => println!("Could not load {}: {}", song, err),
+    }
 }
@@ -1,11 +1,15 @@
+use crate::chart_error::ChartLoadError;
 use crate::consts::*;
-use crate::types::load_config;
+use crate::score::ScoreResource;
+use crate::types::{load_config, load_meta, ChartMeta};
+use crate::user_settings::UserSettings;
 use bevy::a11y::accesskit::{NodeBuilder, Role};
 use bevy::a11y::AccessibilityNode;
 use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use std::ffi::OsStr;
 use std::fs::read_dir;
+use std::path::Path;
 
 #[derive(Resource)]
 struct ButtonMaterials {
@@ -31,26 +35,101 @@ impl FromWorld for ButtonMaterials {
     }
 }
 
+/// Every chart in `assets/songs` with its metadata, read once at startup since parsing all of
+/// them is slow
+#[derive(Resource)]
+struct SongLibrary {
+    songs: Vec<(String, Option<ChartMeta>)>,
+}
+
+impl FromWorld for SongLibrary {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource::<UserSettings>();
+        let default_settings = UserSettings::default();
+        let settings = settings.unwrap_or(&default_settings);
+
+        SongLibrary {
+            songs: get_songs()
+                .into_iter()
+                .filter_map(|name| match load_meta(&name, settings) {
+                    // Charts for other games, like osu!standard maps next to mania ones, can't
+                    // be played at all
+                    Err(ChartLoadError::UnsupportedMode(_)) => None,
+                    meta => Some((name, meta.ok())),
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Component)]
 enum MenuButton {
-    PlaySong(String),
+    /// A chart's path, with its metadata if it could be read
+    PlaySong(String, Option<ChartMeta>),
 }
 impl MenuButton {
     fn name(&self) -> String {
         match self {
-            Self::PlaySong(song) => format!("Play song: {}", song),
+            Self::PlaySong(song, meta) => {
+                let marker =
+                    format_marker(song).map_or(String::new(), |marker| format!("[{}] ", marker));
+
+                match meta {
+                    Some(meta) if !meta.title.is_empty() => format!(
+                        "{}{} / {}\n{}",
+                        marker,
+                        meta.full_title(),
+                        meta.artist,
+                        meta.details_text()
+                    ),
+                    _ => format!("Play song: {}{}", marker, song),
+                }
+            }
         }
     }
 }
 
+/// Short name of the chart format for charts that aren't BMS
+fn format_marker(song: &str) -> Option<&'static str> {
+    let extension = Path::new(song).extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        "bmson" => Some("bmson"),
+        "osu" => Some("osu!mania"),
+        "sm" | "ssc" => Some("StepMania"),
+        _ => None,
+    }
+}
+
 #[derive(Component)]
 struct MenuUI;
 
-fn setup_menu(mut commands: Commands, button_materials: Res<ButtonMaterials>) {
+/// Shows which #RANDOM branch will be forced, if any
+#[derive(Component)]
+struct RandomOverrideText;
+
+/// Shows why the last chart that was picked couldn't be loaded
+#[derive(Component)]
+struct LoadErrorText;
+
+fn random_override_label(settings: &UserSettings) -> String {
+    match settings.random_override {
+        Some(value) => format!("#RANDOM branch: {} (0 to clear)", value),
+        None => "#RANDOM branch: random (1-9 to pick)".to_string(),
+    }
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    button_materials: Res<ButtonMaterials>,
+    settings: Res<UserSettings>,
+    library: Res<SongLibrary>,
+) {
     // Make list of buttons
-    let mut buttons: Vec<MenuButton> = get_songs()
+    let buttons: Vec<MenuButton> = library
+        .songs
         .iter()
-        .map(|name| MenuButton::PlaySong(name.clone()))
+        .map(|(name, meta)| MenuButton::PlaySong(name.clone(), meta.clone()))
         .collect();
 
     commands
@@ -69,6 +148,36 @@ fn setup_menu(mut commands: Commands, button_materials: Res<ButtonMaterials>) {
         })
         .insert(MenuUI)
         .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    text: Text::from_section(
+                        random_override_label(&settings),
+                        TextStyle {
+                            font: button_materials.font.clone(),
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ),
+                    ..default()
+                })
+                .insert(RandomOverrideText);
+
+            parent
+                .spawn(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: button_materials.font.clone(),
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.3, 0.3),
+                            ..default()
+                        },
+                    ),
+                    ..default()
+                })
+                .insert(LoadErrorText);
+
             // Moving panel
             parent
                 .spawn((
@@ -88,7 +197,7 @@ fn setup_menu(mut commands: Commands, button_materials: Res<ButtonMaterials>) {
                         parent1
                             .spawn(ButtonBundle {
                                 style: Style {
-                                    width: Val::Px(350.0),
+                                    width: Val::Px(600.0),
                                     height: Val::Px(65.0),
                                     margin: UiRect::all(Val::Auto),
                                     justify_content: JustifyContent::Center,
@@ -178,20 +287,71 @@ fn button_press_system(
     query: Query<(&Interaction, &MenuButton), (Changed<Interaction>, With<Button>)>,
     // state: ResMut<State<MyAppState>>,
     mut next_state: ResMut<NextState<MyAppState>>,
+    settings: Res<UserSettings>,
+    mut score: ResMut<ScoreResource>,
+    mut error_text: Query<&mut Text, With<LoadErrorText>>,
 ) {
     for (interaction, button) in query.iter() {
         if *interaction == Interaction::Pressed {
             match button {
-                MenuButton::PlaySong(song) => {
-                    let config = load_config(song, &asset_server);
-                    commands.insert_resource(config);
-                    next_state.set(MyAppState::InGame);
+                MenuButton::PlaySong(song, _) => {
+                    match load_config(song, &asset_server, &settings) {
+                        Ok(config) => {
+                            score.random_seed = config.random_seed;
+                            score.random_values = config.random_values.clone();
+                            commands.insert_resource(config);
+                            next_state.set(MyAppState::InGame);
+                        }
+                        Err(err) => {
+                            println!("Could not load {}: {}", song, err);
+                            for mut text in error_text.iter_mut() {
+                                text.sections[0].value =
+                                    format!("Could not load {}: {}", song, err);
+                            }
+                        }
+                    }
                 }
             }
         }
     }
 }
 
+/// Number keys pick the #RANDOM branch to force, 0 goes back to random ones
+fn random_override_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<UserSettings>,
+    mut query: Query<&mut Text, With<RandomOverrideText>>,
+) {
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    let Some(digit) = DIGITS
+        .iter()
+        .position(|code| keyboard_input.just_pressed(*code))
+    else {
+        return;
+    };
+
+    settings.random_override = if digit == 0 { None } else { Some(digit as u32) };
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = random_override_label(&settings);
+    }
+}
+
+/// Extensions of the chart files listed in song select
+const CHART_EXTENSIONS: [&str; 8] = ["bms", "bme", "bml", "pms", "bmson", "osu", "sm", "ssc"];
+
 // probably eventually want to store song data in a DB to avoid having to parse the whole directory all the time
 pub fn get_songs() -> Vec<String> {
     let song_dirs = read_dir("assets/songs").expect("Failed to read songs dir");
@@ -203,7 +363,11 @@ pub fn get_songs() -> Vec<String> {
         for path in paths {
             let path = path.expect("Failed to unwrap path").path();
 
-            if "bms" == path.as_path().extension().unwrap_or(OsStr::new("")) {
+            let extension = path.as_path().extension().unwrap_or(OsStr::new(""));
+            if CHART_EXTENSIONS
+                .iter()
+                .any(|chart_extension| extension.eq_ignore_ascii_case(chart_extension))
+            {
                 let path_stripped_prefix = path
                     .as_path()
                     .strip_prefix("assets/")
@@ -227,12 +391,18 @@ pub struct MenuPlugin<S: States> {
 impl<S: States> Plugin for MenuPlugin<S> {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonMaterials>();
+        app.init_resource::<SongLibrary>();
         // app.add_systems(Startup, setup_menu.run_if(in_state(self.state.clone())));
         app.add_systems(OnEnter(self.state.clone()), setup_menu);
         app.add_systems(OnExit(self.state.clone()), despawn_menu);
         app.add_systems(
             Update,
-            (button_color_system, button_press_system, mouse_scroll)
+            (
+                button_color_system,
+                button_press_system,
+                random_override_system,
+                mouse_scroll,
+            )
                 .run_if(in_state(self.state.clone())),
         );
     }
@@ -1,28 +1,71 @@
+use crate::charset;
+use crate::chart_error::ChartLoadError;
+use crate::chart_random::ChartRng;
+use crate::keysounds::KeysoundResolver;
 use bms_rs::{
-    lex::parse,
-    parse::{rng::RngMock, Bms},
+    lex::{command::ObjId, parse},
+    parse::Bms,
 };
-use encoding_rs::SHIFT_JIS;
+use encoding_rs::Encoding;
 
-pub fn new_parse(filename: &str) -> Bms {
+/// A parsed chart along with the header data bms-rs doesn't keep for us
+pub struct ParsedBms {
+    pub bms: Bms,
+    /// Objects declared with `#LNOBJ`, which end the previous note in their lane
+    pub lnobj_ids: Vec<ObjId>,
+    /// Text encoding the chart was decoded with
+    pub encoding: &'static Encoding,
+}
+
+pub fn new_parse(
+    filename: &str,
+    rng: &mut ChartRng,
+    keysounds: &mut KeysoundResolver,
+) -> Result<ParsedBms, ChartLoadError> {
     let filename_ = format!("assets/{}", filename);
-    let data = std::fs::read(&filename_).expect("Filename not found");
-
-    // First try to decode as UTF-8
-    let source = match std::str::from_utf8(&data) {
-        Ok(v) => v.to_owned(),
-        Err(_) => {
-            // If UTF-8 fails, try SHIFT-JIS
-            let (cow, _encoding_used, _had_errors) = SHIFT_JIS.decode(&data);
-            cow.into_owned()
-        }
-    };
+    let data = std::fs::read(&filename_)?;
+
+    let (source, encoding) =
+        charset::decode_chart(&data, keysounds).ok_or(ChartLoadError::Encoding)?;
 
     // let source = std::fs::read_to_string(filename_).expect("filename not found");
-    let token_stream = parse(&source).expect("Must be parsed");
-    let rng = RngMock([1]);
-    let bms = Bms::from_token_stream(&token_stream, rng).expect("must be parsed");
+    let token_stream = parse(&source).map_err(|err| ChartLoadError::Lex(format!("{:?}", err)))?;
+    let bms = Bms::from_token_stream(&token_stream, rng)
+        .map_err(|err| ChartLoadError::Parse(format!("{:?}", err)))?;
     // println!("{:#?}", bms);
 
-    bms
+    Ok(ParsedBms {
+        bms,
+        lnobj_ids: parse_lnobj_ids(&source),
+        encoding,
+    })
+}
+
+/// Reads a chart under `assets/` that has to be UTF-8, like the JSON and text based formats
+pub fn read_utf8_chart(file_path: &str) -> Result<String, ChartLoadError> {
+    let data = std::fs::read(format!("assets/{}", file_path))?;
+
+    let source = String::from_utf8(data).map_err(|_| ChartLoadError::Encoding)?;
+
+    // Some editors start the file with a byte order mark, which JSON parsers choke on
+    Ok(source
+        .strip_prefix('\u{feff}')
+        .unwrap_or(&source)
+        .to_string())
+}
+
+fn parse_lnobj_ids(source: &str) -> Vec<ObjId> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let command = line.get(..6)?;
+
+            if !command.eq_ignore_ascii_case("#LNOBJ") {
+                return None;
+            }
+
+            ObjId::try_from(line[6..].trim()).ok()
+        })
+        .collect()
 }
@@ -0,0 +1,211 @@
+use crate::chart_error::ChartLoadError;
+use crate::keysounds::KeysoundResolver;
+use crate::layouts::LaneLayout;
+use crate::new_bms_parser::read_utf8_chart;
+use crate::types::{ChartMeta, NoteTime, NoteType, Positions, SongConfig, BGM, MUSIC_ID, NOTE_ID};
+use std::collections::HashMap;
+
+/// Hit object type bit marking a mania hold note
+const HOLD_NOTE: u32 = 128;
+
+/// Maps osu!mania columns from left to right onto lanes, picking the layout by key count
+fn columns_for(keys: usize) -> (LaneLayout, Vec<Positions>) {
+    use Positions::*;
+
+    match keys {
+        5 => (LaneLayout::five_key(), vec![One, Two, Three, Four, Five]),
+        // 8K maps are usually played with the scratch on the leftmost column
+        8 => (
+            LaneLayout::seven_key(),
+            vec![Scratch, One, Two, Three, Four, Five, Six, Seven],
+        ),
+        _ => {
+            if keys != 6 && keys != 7 {
+                println!(
+                    "Warning: {}K maps are not supported, playing the first 7 columns",
+                    keys
+                );
+            }
+            (
+                LaneLayout::seven_key(),
+                vec![One, Two, Three, Four, Five, Six, Seven],
+            )
+        }
+    }
+}
+
+/// Splits an osu! file into its `[Section]`s, keeping each section's lines in order
+fn sections(source: &str) -> HashMap<&str, Vec<&str>> {
+    let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut current = "";
+
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            current = &line[1..line.len() - 1];
+            continue;
+        }
+
+        sections.entry(current).or_default().push(line);
+    }
+
+    sections
+}
+
+/// Value of a `Key: Value` line in a section
+fn section_value<'a>(lines: &[&'a str], key: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let (line_key, value) = line.split_once(':')?;
+        (line_key.trim() == key).then(|| value.trim())
+    })
+}
+
+/// BPM of every uninherited timing point, in the order they appear
+fn timing_point_bpms(lines: &[&str]) -> Vec<f64> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let beat_length: f64 = fields.get(1)?.trim().parse().ok()?;
+
+            // Inherited points only change scroll speed, and have a negative beat length
+            let uninherited = fields.get(6).is_none_or(|field| field.trim() == "1");
+            (uninherited && beat_length > 0.).then_some(60000. / beat_length)
+        })
+        .collect()
+}
+
+pub fn load_config(
+    file_path: &str,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    let source = read_utf8_chart(file_path)?;
+    let sections = sections(&source);
+    let no_lines = Vec::new();
+    let general = sections.get("General").unwrap_or(&no_lines);
+    let difficulty = sections.get("Difficulty").unwrap_or(&no_lines);
+
+    // Beatmap folders often have osu!standard difficulties next to the mania ones
+    match section_value(general, "Mode") {
+        Some("3") => {}
+        mode => {
+            return Err(ChartLoadError::UnsupportedMode(format!(
+                "osu! mode {}, not osu!mania",
+                mode.unwrap_or("0")
+            )))
+        }
+    }
+
+    // CircleSize holds the key count in mania maps
+    let keys = section_value(difficulty, "CircleSize")
+        .and_then(|value| value.parse::<f32>().ok())
+        .map_or(7, |value| value as usize)
+        .max(1);
+    let (layout, columns) = columns_for(keys);
+
+    let bpms = timing_point_bpms(sections.get("TimingPoints").unwrap_or(&no_lines));
+    let metadata = sections.get("Metadata").unwrap_or(&no_lines);
+    // Unicode titles are optional, and left blank in plenty of maps
+    let metadata_value = |unicode_key: &str, key: &str| {
+        section_value(metadata, unicode_key)
+            .filter(|value| !value.is_empty())
+            .or(section_value(metadata, key))
+            .unwrap_or("")
+            .to_string()
+    };
+    let mut meta = ChartMeta {
+        title: metadata_value("TitleUnicode", "Title"),
+        artist: metadata_value("ArtistUnicode", "Artist"),
+        difficulty: section_value(metadata, "Version").map(String::from),
+        rank: section_value(difficulty, "OverallDifficulty").map(|od| format!("OD {}", od)),
+        // Maps without timing points are left at 0 rather than an impossible range
+        min_bpm: bpms.iter().copied().reduce(f64::min).unwrap_or(0.),
+        max_bpm: bpms.iter().copied().reduce(f64::max).unwrap_or(0.),
+        encoding: "UTF-8",
+        ..Default::default()
+    };
+
+    let mut audio_handles_map = HashMap::new();
+    let mut bgms_config_list = Vec::new();
+    if let Some(audio_file) = section_value(general, "AudioFilename") {
+        if let Some(audio_handle) = keysounds.load(audio_file) {
+            audio_handles_map.insert(MUSIC_ID, audio_handle);
+        }
+        bgms_config_list.push(BGM {
+            spawn_time: 0.,
+            audio_source_ids: vec![MUSIC_ID],
+        });
+    }
+
+    let mut notetimes: Vec<NoteTime> = Vec::new();
+    let mut skipped_columns = 0;
+
+    for line in sections.get("HitObjects").unwrap_or(&no_lines) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let (Some(x), Some(time), Some(kind)) = (
+            fields.first().and_then(|x| x.trim().parse::<f64>().ok()),
+            fields
+                .get(2)
+                .and_then(|time| time.trim().parse::<f64>().ok()),
+            fields
+                .get(3)
+                .and_then(|kind| kind.trim().parse::<u32>().ok()),
+        ) else {
+            println!("Warning: could not read hit object {}", line);
+            continue;
+        };
+
+        // Columns split the 512 pixel wide playfield evenly
+        let column = (x * keys as f64 / 512.).floor().clamp(0., keys as f64 - 1.) as usize;
+        let Some(&position) = columns.get(column) else {
+            skipped_columns += 1;
+            continue;
+        };
+
+        // Holds store their end time before the hit sample, as in "endTime:0:0:0:0:"
+        let end_time = fields
+            .get(5)
+            .and_then(|params| params.split(':').next())
+            .and_then(|end_time| end_time.trim().parse::<f64>().ok());
+        let note_type = match end_time {
+            Some(end_time) if kind & HOLD_NOTE != 0 => NoteType::Long {
+                end_time: end_time / 1000.,
+            },
+            _ => NoteType::Normal,
+        };
+
+        notetimes.push(NoteTime {
+            spawn_time: time / 1000.,
+            position,
+            note_type,
+            audio_source_id: NOTE_ID,
+        });
+    }
+
+    if skipped_columns > 0 {
+        println!(
+            "Warning: skipped {} notes in columns past the 7th",
+            skipped_columns
+        );
+    }
+
+    notetimes.sort_by(|a, b| a.spawn_time.total_cmp(&b.spawn_time));
+
+    if notetimes.is_empty() {
+        return Err(ChartLoadError::EmptyChart);
+    }
+    meta.count_notes(&notetimes);
+
+    Ok(SongConfig::new(
+        notetimes,
+        bgms_config_list,
+        Vec::new(),
+        layout,
+        meta,
+        audio_handles_map,
+    ))
+}
@@ -1,5 +1,6 @@
 use crate::consts::MyAppState;
 use crate::score::ScoreResource;
+use crate::types::SongConfig;
 use bevy::prelude::*;
 
 macro_rules! spawn_text_entity {
@@ -34,6 +35,7 @@ fn setup_results_screen(
     mut commands: Commands,
     score: Res<ScoreResource>,
     asset_server: Res<AssetServer>,
+    song_config: Option<Res<SongConfig>>,
 ) {
     commands
         .spawn(NodeBundle {
@@ -49,11 +51,120 @@ fn setup_results_screen(
         })
         .insert(ResultsUI)
         .with_children(|parent| {
+            if let Some(song_config) = &song_config {
+                let meta = &song_config.meta;
+                spawn_text_entity!(
+                    parent,
+                    asset_server,
+                    meta.full_title(),
+                    meta.level_text(),
+                    30.0,
+                    1.0
+                );
+                spawn_text_entity!(parent, asset_server, "GENRE", meta.genre, 30.0, 1.0);
+                spawn_text_entity!(
+                    parent,
+                    asset_server,
+                    "JUDGE",
+                    meta.rank.as_deref().unwrap_or("-"),
+                    30.0,
+                    1.0
+                );
+                spawn_text_entity!(
+                    parent,
+                    asset_server,
+                    "TOTAL",
+                    meta.total
+                        .map_or("-".to_string(), |total| format!("{:.0}", total)),
+                    30.0,
+                    1.0
+                );
+            }
+
             spawn_text_entity!(parent, asset_server, "PGREAT", score.pgreats, 30.0, 1.0);
             spawn_text_entity!(parent, asset_server, "GREAT", score.greats, 30.0, 1.0);
             spawn_text_entity!(parent, asset_server, "GOOD", score.goods, 30.0, 1.0);
             spawn_text_entity!(parent, asset_server, "BAD", score.bads, 30.0, 1.0);
             spawn_text_entity!(parent, asset_server, "POOR", score.poors, 30.0, 1.0);
+            spawn_text_entity!(parent, asset_server, "MISS", score.misses, 24.0, 1.0);
+            spawn_text_entity!(
+                parent,
+                asset_server,
+                "EMPTY POOR",
+                score.empty_poors,
+                24.0,
+                1.0
+            );
+            spawn_text_entity!(
+                parent,
+                asset_server,
+                "MISS COUNT",
+                score.miss_count(),
+                30.0,
+                1.0
+            );
+
+            spawn_text_entity!(
+                parent,
+                asset_server,
+                "FAST / SLOW",
+                format!("{} / {}", score.fasts, score.slows),
+                30.0,
+                1.0
+            );
+            if let Some(offset) = score.average_offset() {
+                spawn_text_entity!(
+                    parent,
+                    asset_server,
+                    "AVERAGE OFFSET",
+                    format!("{:+.1} ms", offset),
+                    24.0,
+                    1.0
+                );
+            }
+
+            if score.ln_breaks > 0 {
+                spawn_text_entity!(parent, asset_server, "LN BREAK", score.ln_breaks, 24.0, 1.0);
+            }
+
+            // Only charge note modes judge tails
+            if score.tail_judgements() > 0 {
+                for (text, count) in [
+                    ("CN PGREAT", score.tail_pgreats),
+                    ("CN GREAT", score.tail_greats),
+                    ("CN GOOD", score.tail_goods),
+                    ("CN BAD", score.tail_bads),
+                    ("CN POOR", score.tail_poors),
+                ] {
+                    spawn_text_entity!(parent, asset_server, text, count, 24.0, 1.0);
+                }
+            }
+
+            if !score.random_values.is_empty() {
+                let values: Vec<String> = score
+                    .random_values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect();
+
+                spawn_text_entity!(
+                    parent,
+                    asset_server,
+                    "RANDOM",
+                    format!("{} (seed {})", values.join(", "), score.random_seed),
+                    24.0,
+                    1.0
+                );
+            }
+
+            spawn_text_entity!(
+                parent,
+                asset_server,
+                "GAUGE",
+                format!("{:.0}%", score.gauge.value()),
+                30.0,
+                1.0
+            );
         });
 }
 
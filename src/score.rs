@@ -1,6 +1,111 @@
 use crate::consts::*;
 use bevy::prelude::*;
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Judgement {
+    PGreat,
+    Great,
+    Good,
+    Bad,
+    Poor,
+}
+
+/// How many milliseconds away from a note a hit can be for each judgement
+#[derive(Copy, Clone, Debug)]
+pub struct JudgeWindows {
+    pub pgreat: f32,
+    pub great: f32,
+    pub good: f32,
+    pub bad: f32,
+    /// Pressing this early before a note, but outside the bad window, is an empty POOR. Notes more
+    /// than the bad window late are missed.
+    pub poor: f32,
+}
+
+impl Default for JudgeWindows {
+    fn default() -> Self {
+        // using mostly IIDX timings for now
+        JudgeWindows {
+            pgreat: 16.67,
+            great: 33.33,
+            good: 116.67,
+            bad: 250.,
+            poor: 500.,
+        }
+    }
+}
+
+impl Judgement {
+    /// Judges a hit by how many milliseconds it was away from the target, or None if it's outside
+    /// the bad window. POOR only comes from misses and empty presses.
+    pub fn from_milliseconds(msval: f32, windows: &JudgeWindows) -> Option<Self> {
+        let msval = msval.abs();
+
+        if msval <= windows.pgreat {
+            Some(Judgement::PGreat)
+        } else if msval <= windows.great {
+            Some(Judgement::Great)
+        } else if msval <= windows.good {
+            Some(Judgement::Good)
+        } else if msval <= windows.bad {
+            Some(Judgement::Bad)
+        } else {
+            None
+        }
+    }
+
+    /// How much this judgement moves the gauge, in percent
+    fn gauge_change(&self) -> f32 {
+        match self {
+            Judgement::PGreat | Judgement::Great => 0.8,
+            Judgement::Good => 0.4,
+            Judgement::Bad => -2.,
+            Judgement::Poor => -6.,
+        }
+    }
+}
+
+/// Whether a hit came before or after the note's time, for hits that weren't PGREAT
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Timing {
+    Fast,
+    Slow,
+}
+
+impl Timing {
+    /// Timing of a hit `msval` milliseconds from its note, negative being early
+    pub fn of(judgement: Judgement, msval: f32) -> Option<Self> {
+        if judgement == Judgement::PGreat || msval == 0. {
+            None
+        } else if msval < 0. {
+            Some(Timing::Fast)
+        } else {
+            Some(Timing::Slow)
+        }
+    }
+}
+
+/// Groove gauge percentage
+#[derive(Copy, Clone, Debug)]
+pub struct Gauge(f32);
+
+impl Default for Gauge {
+    fn default() -> Self {
+        Gauge(GAUGE_START)
+    }
+}
+
+impl Gauge {
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+
+    /// Adds `amount` to the gauge, keeping it between the minimum and 100%
+    pub fn change(&mut self, amount: f32) {
+        self.0 = (self.0 + amount).clamp(GAUGE_MIN, 100.);
+    }
+}
+
 #[derive(Default, Resource)]
 pub struct ScoreResource {
     corrects: usize,
@@ -10,16 +115,39 @@ pub struct ScoreResource {
     pub greats: usize,
     pub goods: usize,
     pub bads: usize,
+    /// Both misses and empty POORs, as IIDX counts them
     pub poors: usize,
+    /// Notes that passed the bad window without being hit
+    pub misses: usize,
+    /// Presses with no note in the bad window but one approaching within the poor window
+    pub empty_poors: usize,
+    /// Judgements of charge note releases, counted apart from the note heads
+    pub tail_pgreats: usize,
+    pub tail_greats: usize,
+    pub tail_goods: usize,
+    pub tail_bads: usize,
+    pub tail_poors: usize,
+    /// Long notes let go of before their end in LN mode, which only judges the head
+    pub ln_breaks: usize,
+    pub gauge: Gauge,
+    /// Signed offset of every judged hit in milliseconds, negative when early
+    pub offsets: Vec<f32>,
+    pub fasts: usize,
+    pub slows: usize,
+    /// Timing of the latest judged hit, None if it was a PGREAT
+    pub last_timing: Option<Timing>,
+    /// Seed and picked values of the chart's #RANDOM branches, so a play can be reproduced
+    pub random_seed: u64,
+    pub random_values: Vec<u32>,
 }
 
 impl ScoreResource {
-    /// Increases number of corrects and adds to score
-    pub fn increase_correct(&mut self, distance: f32) -> usize {
+    /// Increases number of corrects and adds to score, `msval` being how far off the hit was
+    pub fn increase_correct(&mut self, msval: f32, windows: &JudgeWindows) -> usize {
         self.corrects += 1;
 
         // Get a value from 0 to 1 according to how close the press was
-        let score_multiplier = (THRESHOLD - distance.abs()) / THRESHOLD;
+        let score_multiplier = (windows.bad - msval.abs()) / windows.bad;
 
         // Give at least 10 points and 100 at max
         let points = (score_multiplier * 100.).min(100.).max(10.) as usize;
@@ -33,6 +161,84 @@ impl ScoreResource {
         self.fails += 1;
     }
 
+    /// Counts the judgement of a note and applies it to the gauge
+    pub fn add_judgement(&mut self, judgement: Judgement) {
+        match judgement {
+            Judgement::PGreat => self.pgreats += 1,
+            Judgement::Great => self.greats += 1,
+            Judgement::Good => self.goods += 1,
+            Judgement::Bad => self.bads += 1,
+            Judgement::Poor => self.poors += 1,
+        }
+
+        self.gauge.change(judgement.gauge_change());
+    }
+
+    /// Counts a note that passed without being hit
+    pub fn add_miss(&mut self) {
+        self.misses += 1;
+        self.increase_fails();
+        self.add_judgement(Judgement::Poor);
+    }
+
+    /// Counts a press on a note that was still too far away to be hit
+    pub fn add_empty_poor(&mut self) {
+        self.empty_poors += 1;
+        self.poors += 1;
+        self.gauge.change(-EMPTY_POOR_GAUGE_DAMAGE);
+    }
+
+    /// Miss count as IIDX shows it, BADs and missed notes but not empty POORs
+    pub fn miss_count(&self) -> usize {
+        self.bads + self.misses
+    }
+
+    /// Counts the judgement of a charge note tail and applies it to the gauge
+    pub fn add_tail_judgement(&mut self, judgement: Judgement) {
+        match judgement {
+            Judgement::PGreat => self.tail_pgreats += 1,
+            Judgement::Great => self.tail_greats += 1,
+            Judgement::Good => self.tail_goods += 1,
+            Judgement::Bad => self.tail_bads += 1,
+            Judgement::Poor => self.tail_poors += 1,
+        }
+
+        self.gauge.change(judgement.gauge_change());
+    }
+
+    /// Records how early or late a judged hit was, `msval` being negative when early
+    pub fn add_timing(&mut self, judgement: Judgement, msval: f32) {
+        self.offsets.push(msval);
+        self.last_timing = Timing::of(judgement, msval);
+
+        match self.last_timing {
+            Some(Timing::Fast) => self.fasts += 1,
+            Some(Timing::Slow) => self.slows += 1,
+            None => {}
+        }
+    }
+
+    /// Mean offset of all judged hits in milliseconds, None before anything was hit
+    pub fn average_offset(&self) -> Option<f32> {
+        if self.offsets.is_empty() {
+            return None;
+        }
+
+        Some(self.offsets.iter().sum::<f32>() / self.offsets.len() as f32)
+    }
+
+    /// Counts a long note released too early in LN mode, hitting the gauge like a BAD
+    pub fn add_ln_break(&mut self) {
+        self.ln_breaks += 1;
+        self.increase_fails();
+        self.gauge.change(Judgement::Bad.gauge_change());
+    }
+
+    /// Total number of charge note tails judged
+    pub fn tail_judgements(&self) -> usize {
+        self.tail_pgreats + self.tail_greats + self.tail_goods + self.tail_bads + self.tail_poors
+    }
+
     pub fn reset(&mut self) {
         self.corrects = 0;
         self.fails = 0;
@@ -42,6 +248,21 @@ impl ScoreResource {
         self.goods = 0;
         self.bads = 0;
         self.poors = 0;
+        self.misses = 0;
+        self.empty_poors = 0;
+        self.tail_pgreats = 0;
+        self.tail_greats = 0;
+        self.tail_goods = 0;
+        self.tail_bads = 0;
+        self.tail_poors = 0;
+        self.ln_breaks = 0;
+        self.gauge = Gauge::default();
+        self.offsets.clear();
+        self.fasts = 0;
+        self.slows = 0;
+        self.last_timing = None;
+        self.random_seed = 0;
+        self.random_values.clear();
     }
 
     // Getters -- this seems stupid but i'm just following the tutorial for now
@@ -0,0 +1,313 @@
+use crate::chart_error::ChartLoadError;
+use crate::keysounds::KeysoundResolver;
+use crate::layouts::LaneLayout;
+use crate::new_bms_parser::read_utf8_chart;
+use crate::timing::TimingMap;
+use crate::types::{
+    ChartMeta, NoteTime, NoteType, Positions, SongConfig, StopTime, BGM, MUSIC_ID, NOTE_ID,
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Gauge damage of a StepMania mine, in percent
+const MINE_DAMAGE: f32 = 6.;
+
+/// One chart of a simfile, with the timing tags it overrides (.ssc charts can have their own)
+#[derive(Debug, Default)]
+struct Chart<'a> {
+    steps_type: &'a str,
+    difficulty: &'a str,
+    meter: u32,
+    notes: &'a str,
+    bpms: Option<&'a str>,
+    stops: Option<&'a str>,
+    offset: Option<&'a str>,
+}
+
+/// Splits a simfile into its `#TAG:value;` pairs, in order. Tags are upper cased.
+fn tags(source: &str) -> Vec<(String, &str)> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find('#') {
+        rest = &rest[start + 1..];
+        let Some(colon) = rest.find(':') else {
+            break;
+        };
+        let end = rest[colon..]
+            .find(';')
+            .map_or(rest.len(), |end| colon + end);
+
+        tags.push((
+            rest[..colon].trim().to_uppercase(),
+            rest[colon + 1..end].trim(),
+        ));
+        rest = &rest[end..];
+    }
+
+    tags
+}
+
+/// Collects the charts of a .sm or .ssc file, along with the song's own tags
+fn charts<'a>(tags: &'a [(String, &'a str)]) -> (HashMap<&'a str, &'a str>, Vec<Chart<'a>>) {
+    let mut song_tags = HashMap::new();
+    let mut charts: Vec<Chart> = Vec::new();
+    // .ssc charts start with #NOTEDATA and list their fields as tags until the next one
+    let mut in_ssc_chart = false;
+
+    for (tag, value) in tags {
+        match tag.as_str() {
+            "NOTEDATA" => {
+                in_ssc_chart = true;
+                charts.push(Chart::default());
+            }
+            // .sm charts keep their fields in the #NOTES value, separated by colons
+            "NOTES" if !in_ssc_chart => {
+                let fields: Vec<&str> = value.splitn(6, ':').map(str::trim).collect();
+                if let [steps_type, _description, difficulty, meter, _radar, notes] = fields[..] {
+                    charts.push(Chart {
+                        steps_type,
+                        difficulty,
+                        meter: meter.parse().unwrap_or(0),
+                        notes,
+                        ..default()
+                    });
+                }
+            }
+            _ if in_ssc_chart => {
+                let chart = charts.last_mut().unwrap();
+                match tag.as_str() {
+                    "STEPSTYPE" => chart.steps_type = value,
+                    "DIFFICULTY" => chart.difficulty = value,
+                    "METER" => chart.meter = value.parse().unwrap_or(0),
+                    "NOTES" => chart.notes = value,
+                    "BPMS" => chart.bpms = Some(value),
+                    "STOPS" => chart.stops = Some(value),
+                    "OFFSET" => chart.offset = Some(value),
+                    _ => {}
+                }
+            }
+            _ => {
+                song_tags.insert(tag.as_str(), *value);
+            }
+        }
+    }
+
+    (song_tags, charts)
+}
+
+/// Reads a `beat=value,beat=value` list like #BPMS and #STOPS
+fn beat_values(value: &str) -> Vec<(f64, f64)> {
+    let mut values: Vec<(f64, f64)> = value
+        .split(',')
+        .filter_map(|pair| {
+            let (beat, value) = pair.split_once('=')?;
+            Some((beat.trim().parse().ok()?, value.trim().parse().ok()?))
+        })
+        .collect();
+
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+    values
+}
+
+fn layout_for(steps_type: &str) -> Option<LaneLayout> {
+    match steps_type {
+        "dance-single" => Some(LaneLayout::four_panel()),
+        "pump-single" => Some(LaneLayout::five_panel()),
+        _ => None,
+    }
+}
+
+pub fn load_config(
+    file_path: &str,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    load_simfile(&read_utf8_chart(file_path)?, keysounds)
+}
+
+/// Loads the hardest playable chart of a simfile's text
+fn load_simfile(
+    source: &str,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    // Comments can be anywhere, even in the middle of note data
+    let source: String = source
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .collect::<Vec<&str>>()
+        .join("\n");
+    let tags = tags(&source);
+    let (song_tags, charts) = charts(&tags);
+
+    // Play the hardest chart we have a layout for
+    let (chart, layout) = charts
+        .iter()
+        .filter_map(|chart| Some((chart, layout_for(chart.steps_type)?)))
+        .max_by_key(|(chart, _)| chart.meter)
+        .ok_or(ChartLoadError::EmptyChart)?;
+
+    let bpms = beat_values(chart.bpms.or(song_tags.get("BPMS").copied()).unwrap_or(""));
+    // The timing map starts at beat 0, so the BPM in effect there is the initial one and only
+    // the entries after it are changes
+    let initial_bpm = bpms
+        .iter()
+        .rfind(|(beat, _)| *beat <= 0.)
+        .map(|(_, bpm)| *bpm)
+        .filter(|bpm| *bpm > 0.)
+        .ok_or(ChartLoadError::MissingBpm)?;
+    let bpms: Vec<(f64, f64)> = bpms.into_iter().filter(|(beat, _)| *beat > 0.).collect();
+    let bpm_map = TimingMap::from_beats(initial_bpm, &bpms, &[]);
+
+    // Stops are in seconds here, but the timing map wants them in beats. Negative stops (warps
+    // in older simfiles) are skipped by the timing map.
+    let stops: Vec<(f64, f64)> = beat_values(
+        chart
+            .stops
+            .or(song_tags.get("STOPS").copied())
+            .unwrap_or(""),
+    )
+    .into_iter()
+    .map(|(beat, seconds)| (beat, seconds * bpm_map.bpm_at_beat(beat) / 60.))
+    .collect();
+    let timing_map = TimingMap::from_beats(initial_bpm, &bpms, &stops);
+
+    // Beat 0 is at minus #OFFSET seconds into the music
+    let offset: f64 = chart
+        .offset
+        .or(song_tags.get("OFFSET").copied())
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0.);
+    let seconds_at = |beat: f64| timing_map.seconds_at_beat(beat) - offset;
+
+    let mut audio_handles_map = HashMap::new();
+    let mut bgms_config_list = Vec::new();
+    if let Some(music) = song_tags.get("MUSIC").filter(|music| !music.is_empty()) {
+        if let Some(audio_handle) = keysounds.load(music) {
+            audio_handles_map.insert(MUSIC_ID, audio_handle);
+        }
+        bgms_config_list.push(BGM {
+            spawn_time: 0.,
+            audio_source_ids: vec![MUSIC_ID],
+        });
+    }
+
+    let positions: Vec<Positions> = layout.positions().collect();
+    let mut notetimes: Vec<NoteTime> = Vec::new();
+    // Index into `notetimes` of the hold head waiting for its tail in each lane
+    let mut hold_heads: HashMap<Positions, usize> = HashMap::new();
+
+    // Measures are split by commas, and their rows split a 4 beat measure evenly
+    for (measure, rows) in chart.notes.split(',').enumerate() {
+        let rows: Vec<&str> = rows.split_whitespace().collect();
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let beat = measure as f64 * 4. + row_index as f64 * 4. / rows.len() as f64;
+            let spawn_time = seconds_at(beat);
+
+            for (panel, note) in row.chars().enumerate() {
+                let Some(&position) = positions.get(panel) else {
+                    continue;
+                };
+
+                let note_type = match note {
+                    // Taps and lifts are both hit when they reach the target
+                    '1' | 'L' => NoteType::Normal,
+                    // Hold and roll heads, rolls are played like holds
+                    '2' | '4' => {
+                        hold_heads.insert(position, notetimes.len());
+                        NoteType::Normal
+                    }
+                    '3' => {
+                        if let Some(index) = hold_heads.remove(&position) {
+                            notetimes[index].note_type = NoteType::Long {
+                                end_time: spawn_time,
+                            };
+                        }
+                        continue;
+                    }
+                    'M' => NoteType::Mine {
+                        damage: MINE_DAMAGE,
+                    },
+                    // Empty panels and fakes, which can't be hit
+                    _ => continue,
+                };
+
+                notetimes.push(NoteTime {
+                    spawn_time,
+                    position,
+                    note_type,
+                    audio_source_id: NOTE_ID,
+                });
+            }
+        }
+    }
+
+    if notetimes.is_empty() {
+        return Err(ChartLoadError::EmptyChart);
+    }
+
+    let (min_bpm, max_bpm) = timing_map.bpm_range();
+    let song_tag = |tag: &str| song_tags.get(tag).copied().unwrap_or("").to_string();
+    let mut meta = ChartMeta {
+        title: song_tag("TITLE"),
+        subtitle: song_tag("SUBTITLE"),
+        artist: song_tag("ARTIST"),
+        genre: song_tag("GENRE"),
+        level: Some(chart.meter),
+        difficulty: Some(chart.difficulty.to_string()),
+        min_bpm,
+        max_bpm,
+        encoding: "UTF-8",
+        ..default()
+    };
+    meta.count_notes(&notetimes);
+
+    Ok(SongConfig::new(
+        notetimes,
+        bgms_config_list,
+        timing_map
+            .stop_times()
+            .into_iter()
+            .map(|stop| StopTime {
+                start_time: stop.start_time - offset,
+                duration: stop.duration,
+            })
+            .collect(),
+        layout,
+        meta,
+        audio_handles_map,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_negative_stops() {
+        // Older simfiles make warps out of negative stops
+        let source = "#TITLE:Warp;
+#BPMS:0.000=120.000;
+#STOPS:4.000=-1.000;
+#NOTES:
+     dance-single:
+     :
+     Challenge:
+     10:
+     0,0,0,0,0:
+1000
+,
+1000
+,
+0100
+;
+";
+        let mut keysounds = KeysoundResolver::without_loading("songs/missing/chart.sm", &[]);
+        let config = load_simfile(source, &mut keysounds).expect("could not load simfile");
+
+        assert!(config.stops.is_empty());
+        let spawn_times: Vec<f64> = config.notes.iter().map(|note| note.spawn_time).collect();
+        assert_eq!(spawn_times, vec![0., 2., 4.]);
+        assert_eq!(config.scroll_time(3.), 3.);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::types::StopTime;
+use bms_rs::parse::obj::ObjTime;
+use bms_rs::parse::Bms;
+use std::collections::BTreeMap;
+
+/// A point where the tempo changes, stored in both beats and seconds
+#[derive(Clone, Debug)]
+struct TempoPoint {
+    beat: f64,
+    seconds: f64,
+    bpm: f64,
+}
+
+/// A scroll stop, with its duration already converted to seconds
+#[derive(Clone, Debug)]
+struct StopPoint {
+    beat: f64,
+    duration: f64,
+}
+
+/// Converts chart positions (`ObjTime` or beats) into absolute seconds from the start of the song
+#[derive(Clone, Debug)]
+pub struct TimingMap {
+    tempo_points: Vec<TempoPoint>,
+    /// Length factor of each measure that isn't 4/4 (channel 02), keyed by track number
+    section_lengths: BTreeMap<u32, f64>,
+    stop_points: Vec<StopPoint>,
+}
+
+impl TimingMap {
+    /// Builds the timing map from the parsed chart, starting at the chart's validated #BPM.
+    ///
+    /// bms-rs merges the inline hex BPM changes (channel 03) and the extended `#BPMxx` table
+    /// (channel 08) into `bpm_changes`, so walking that map covers both.
+    pub fn from_bms(bms: &Bms, initial_bpm: f64) -> Self {
+        let section_lengths: BTreeMap<u32, f64> = bms
+            .notes
+            .section_len_changes()
+            .values()
+            .filter(|change| change.length > 0.)
+            .map(|change| (change.track.0, change.length))
+            .collect();
+
+        let mut timing_map = TimingMap {
+            tempo_points: Vec::new(),
+            section_lengths,
+            stop_points: Vec::new(),
+        };
+
+        let bpm_changes: Vec<(f64, f64)> = bms
+            .notes
+            .bpm_changes()
+            .values()
+            .map(|change| (timing_map.beat_at(&change.time), change.bpm))
+            .collect();
+
+        // Stop durations are in 1/192 of a 4/4 measure, so 48 units make a beat
+        let stops: Vec<(f64, f64)> = bms
+            .notes
+            .stops()
+            .values()
+            .map(|stop| (timing_map.beat_at(&stop.time), stop.duration as f64 / 48.))
+            .collect();
+
+        timing_map.add_events(initial_bpm, &bpm_changes, &stops);
+        timing_map
+    }
+
+    /// Builds the timing map from events that are already placed in beats, as (beat, bpm) for
+    /// tempo changes and (beat, length in beats) for stops. Both have to be in time order.
+    pub fn from_beats(initial_bpm: f64, bpm_changes: &[(f64, f64)], stops: &[(f64, f64)]) -> Self {
+        let mut timing_map = TimingMap {
+            tempo_points: Vec::new(),
+            section_lengths: BTreeMap::new(),
+            stop_points: Vec::new(),
+        };
+
+        timing_map.add_events(initial_bpm, bpm_changes, stops);
+        timing_map
+    }
+
+    fn add_events(&mut self, initial_bpm: f64, bpm_changes: &[(f64, f64)], stops: &[(f64, f64)]) {
+        let mut tempo_points = vec![TempoPoint {
+            beat: 0.,
+            seconds: 0.,
+            bpm: initial_bpm,
+        }];
+
+        for &(beat, bpm) in bpm_changes {
+            // Zero or negative BPMs can't be scrolled through, so skip them
+            if bpm <= 0. {
+                continue;
+            }
+
+            let last = tempo_points.last().unwrap();
+            let seconds = last.seconds + (beat - last.beat) * 60. / last.bpm;
+
+            tempo_points.push(TempoPoint { beat, seconds, bpm });
+        }
+
+        self.tempo_points = tempo_points;
+
+        // Stops last a number of beats at the BPM in effect where they are placed
+        for &(beat, length) in stops {
+            // Zero length stops do nothing, and negative ones would scroll backwards, so skip them
+            if length <= 0. {
+                continue;
+            }
+
+            let duration = length * 60. / self.bpm_at_beat(beat);
+
+            self.stop_points.push(StopPoint { beat, duration });
+        }
+    }
+
+    /// Length of a measure in beats, where a 4/4 measure is 4 beats
+    fn measure_beats(&self, track: u32) -> f64 {
+        self.section_lengths.get(&track).copied().unwrap_or(1.) * 4.
+    }
+
+    /// Position of an object in beats from the start of the chart
+    fn beat_at(&self, time: &ObjTime) -> f64 {
+        let track = time.track.0;
+
+        // Every measure before this one is 4 beats, plus the difference of the ones that aren't
+        let mut measure_start = track as f64 * 4.;
+        for (_, length) in self.section_lengths.range(..track) {
+            measure_start += (length - 1.) * 4.;
+        }
+
+        let fraction = time.numerator as f64 / time.denominator as f64;
+        measure_start + fraction * self.measure_beats(track)
+    }
+
+    /// Absolute time in seconds at which an object should be hit
+    pub fn seconds_at(&self, time: &ObjTime) -> f64 {
+        self.seconds_at_beat(self.beat_at(time))
+    }
+
+    /// Absolute time in seconds of a position in beats, including the stops before it
+    pub fn seconds_at_beat(&self, beat: f64) -> f64 {
+        // Objects placed exactly on a stop are played before the stop starts
+        let stopped: f64 = self
+            .stop_points
+            .iter()
+            .filter(|stop| stop.beat < beat)
+            .map(|stop| stop.duration)
+            .sum();
+
+        self.tempo_seconds_at_beat(beat) + stopped
+    }
+
+    /// Lowest and highest BPM the chart plays at
+    pub fn bpm_range(&self) -> (f64, f64) {
+        // A point replaced by another at the same beat (like #BPM overridden at #00000) is
+        // never played
+        let played = self
+            .tempo_points
+            .windows(2)
+            .filter(|pair| pair[0].beat != pair[1].beat)
+            .map(|pair| &pair[0])
+            .chain(self.tempo_points.last());
+
+        played.fold((f64::INFINITY, 0.), |(min, max), point| {
+            (min.min(point.bpm), max.max(point.bpm))
+        })
+    }
+
+    /// Start time and duration of every stop, in absolute seconds
+    pub fn stop_times(&self) -> Vec<StopTime> {
+        let mut stopped = 0.;
+        let mut stop_times = Vec::new();
+
+        for stop in &self.stop_points {
+            stop_times.push(StopTime {
+                start_time: self.tempo_seconds_at_beat(stop.beat) + stopped,
+                duration: stop.duration,
+            });
+            stopped += stop.duration;
+        }
+
+        stop_times
+    }
+
+    /// The tempo point in effect at `beat`
+    fn tempo_point_at_beat(&self, beat: f64) -> &TempoPoint {
+        // The first point is always at beat 0, so there is always a point at or before `beat`
+        let index = self
+            .tempo_points
+            .partition_point(|point| point.beat <= beat)
+            .max(1);
+
+        &self.tempo_points[index - 1]
+    }
+
+    pub fn bpm_at_beat(&self, beat: f64) -> f64 {
+        self.tempo_point_at_beat(beat).bpm
+    }
+
+    /// Time at `beat` from tempo alone, without stops
+    fn tempo_seconds_at_beat(&self, beat: f64) -> f64 {
+        let point = self.tempo_point_at_beat(beat);
+        point.seconds + (beat - point.beat) * 60. / point.bpm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::LaneLayout;
+    use crate::types::{ChartMeta, SongConfig};
+    use std::collections::HashMap;
+
+    /// Compares times in seconds, which go through a few float operations
+    fn assert_seconds(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {} seconds, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn bpm_change() {
+        // One measure at 120 BPM, then 240 BPM from beat 4
+        let timing_map = TimingMap::from_beats(120., &[(4., 240.)], &[]);
+
+        assert_seconds(timing_map.seconds_at_beat(2.), 1.);
+        assert_seconds(timing_map.seconds_at_beat(4.), 2.);
+        assert_seconds(timing_map.seconds_at_beat(8.), 3.);
+        assert_eq!(timing_map.bpm_at_beat(3.9), 120.);
+        assert_eq!(timing_map.bpm_at_beat(4.), 240.);
+        assert_eq!(timing_map.bpm_range(), (120., 240.));
+    }
+
+    #[test]
+    fn bpm_replaced_at_start() {
+        // The header BPM is replaced on beat 0, so it's never played
+        let timing_map = TimingMap::from_beats(150., &[(0., 120.), (8., 180.)], &[]);
+
+        assert_eq!(timing_map.bpm_at_beat(0.), 120.);
+        assert_eq!(timing_map.bpm_range(), (120., 180.));
+    }
+
+    #[test]
+    fn three_four_measure() {
+        // Measure 1 is 3/4, the rest are 4/4, all at 120 BPM
+        let timing_map = TimingMap {
+            section_lengths: BTreeMap::from([(1, 0.75)]),
+            ..TimingMap::from_beats(120., &[], &[])
+        };
+
+        assert_eq!(timing_map.measure_beats(1), 3.);
+        assert_seconds(timing_map.seconds_at(&ObjTime::new(1, 0, 1)), 2.);
+        assert_seconds(timing_map.seconds_at(&ObjTime::new(1, 1, 2)), 2.75);
+        // Measure 2 starts one beat earlier than it would after a 4/4 measure
+        assert_seconds(timing_map.seconds_at(&ObjTime::new(2, 0, 1)), 3.5);
+        assert_seconds(timing_map.seconds_at(&ObjTime::new(3, 0, 1)), 5.5);
+    }
+
+    #[test]
+    fn stop_on_a_note() {
+        // A two beat stop on beat 4 at 120 BPM, which lasts one second
+        let timing_map = TimingMap::from_beats(120., &[], &[(4., 2.)]);
+
+        // The note on the stop is played before it, the ones after are pushed back
+        assert_seconds(timing_map.seconds_at_beat(4.), 2.);
+        assert_seconds(timing_map.seconds_at_beat(5.), 3.5);
+
+        let stop_times = timing_map.stop_times();
+        assert_eq!(stop_times.len(), 1);
+        assert_seconds(stop_times[0].start_time, 2.);
+        assert_seconds(stop_times[0].duration, 1.);
+
+        // Scrolling holds still during the stop and picks up again after it
+        let song_config = SongConfig::new(
+            Vec::new(),
+            Vec::new(),
+            stop_times,
+            LaneLayout::seven_key(),
+            ChartMeta::default(),
+            HashMap::new(),
+        );
+        assert_seconds(song_config.scroll_time(2.), 2.);
+        assert_seconds(song_config.scroll_time(2.5), 2.);
+        assert_seconds(song_config.scroll_time(3.5), 2.5);
+    }
+}
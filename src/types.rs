@@ -1,16 +1,28 @@
+use crate::bmson;
+use crate::chart_error::ChartLoadError;
+use crate::chart_random::ChartRng;
 use crate::consts::*;
+use crate::keysounds::KeysoundResolver;
+use crate::layouts::LaneLayout;
 use crate::new_bms_parser;
+use crate::osu;
+use crate::stepmania;
+use crate::timing::TimingMap;
+use crate::user_settings::UserSettings;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
+use bms_rs::lex::command::JudgeLevel;
 use bms_rs::lex::command::Key;
+use bms_rs::lex::command::NoteKind;
 use bms_rs::lex::command::ObjId;
+use bms_rs::parse::obj::Obj;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 // #[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Positions {
     One,
     Two,
@@ -19,55 +31,59 @@ pub enum Positions {
     Five,
     Six,
     Seven,
+    // Only used by 9 key layouts
+    Eight,
+    Nine,
     Scratch,
+    // 2P side lanes, only used in DP
+    P2One,
+    P2Two,
+    P2Three,
+    P2Four,
+    P2Five,
+    P2Six,
+    P2Seven,
+    P2Scratch,
+}
+/// What kind of note this is and any extra timing it needs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoteType {
+    Normal,
+    /// A note that has to be held until `end_time`
+    Long {
+        end_time: f64,
+    },
+    /// A landmine, which takes `damage` percent off the gauge if its lane is held as it passes
+    Mine {
+        damage: f32,
+    },
 }
-impl Positions {
-    /// Checks if a key that corresponds to this direction has been pressed
-    pub fn key_just_pressed(&self, input: &ButtonInput<KeyCode>) -> bool {
-        let keys: Vec<KeyCode> = match self {
-            Positions::One => vec![KeyCode::KeyA],
-            Positions::Two => vec![KeyCode::KeyS],
-            Positions::Three => vec![KeyCode::KeyD],
-            Positions::Four => vec![KeyCode::Space],
-            Positions::Five => vec![KeyCode::KeyJ],
-            Positions::Six => vec![KeyCode::KeyK],
-            Positions::Seven => vec![KeyCode::KeyL],
-            Positions::Scratch => vec![KeyCode::ShiftLeft, KeyCode::Semicolon],
-        };
-
-        keys.iter().any(|code| input.just_pressed(*code))
-    }
 
-    /// Checks if a key that corresponds to this direction is being pressed
-    pub fn key_pressed(&self, input: &ButtonInput<KeyCode>) -> bool {
-        let keys = match self {
-            Positions::One => vec![KeyCode::KeyA],
-            Positions::Two => vec![KeyCode::KeyS],
-            Positions::Three => vec![KeyCode::KeyD],
-            Positions::Four => vec![KeyCode::Space],
-            Positions::Five => vec![KeyCode::KeyJ],
-            Positions::Six => vec![KeyCode::KeyK],
-            Positions::Seven => vec![KeyCode::KeyL],
-            Positions::Scratch => vec![KeyCode::ShiftLeft, KeyCode::Semicolon],
-        };
+/// A sound that notes and BGMs play. BMS charts name their sounds with object ids, the other
+/// formats number them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SoundId(pub u32);
 
-        // println!("is {:#?} pressed?", input);
-        keys.iter().any(|code| input.pressed(*code))
+impl From<ObjId> for SoundId {
+    fn from(id: ObjId) -> Self {
+        SoundId(u16::from(id) as u32)
     }
+}
 
-    /// Returns the correct x coordinate for a bar with this position
-    pub fn x(&self) -> f32 {
-        match self {
-            Positions::One => -300.,
-            Positions::Two => -200.,
-            Positions::Three => -100.,
-            Positions::Four => 0.,
-            Positions::Five => 100.,
-            Positions::Six => 200.,
-            Positions::Seven => 300.,
-            Positions::Scratch => -400.,
-        }
-    }
+/// Sound the song's music file is loaded under, for formats that play one file under their notes.
+/// It's past any id a chart can use, so it never collides with a chart's own sounds.
+pub const MUSIC_ID: SoundId = SoundId(u32::MAX);
+/// Sound given to notes of formats without keysounds. Nothing is ever loaded under it, so these
+/// notes play no sound.
+pub const NOTE_ID: SoundId = SoundId(u32::MAX - 1);
+
+/// The part of its file a sound plays, in seconds, for formats that cut one file into many
+/// sounds (bmson)
+#[derive(Copy, Clone, Debug)]
+pub struct AudioSlice {
+    pub start: f64,
+    /// Where the sound gets cut off, None to play until the end of the file
+    pub end: Option<f64>,
 }
 
 #[derive(Clone, Debug)]
@@ -75,51 +91,438 @@ impl Positions {
 pub struct NoteTime {
     pub spawn_time: f64,
     pub position: Positions,
+    pub note_type: NoteType,
     // pub audio_source: Handle<AudioSource>,
-    pub audio_source_id: ObjId,
+    pub audio_source_id: SoundId,
 }
 
 #[derive(Clone, Debug)]
 pub struct BGM {
     pub spawn_time: f64,
     // pub audio_sources: Vec<Handle<AudioSource>>,
-    pub audio_source_ids: Vec<ObjId>,
+    pub audio_source_ids: Vec<SoundId>,
+}
+
+/// Keysounds of the invisible notes (channels 3x) in each lane, ordered by time
+#[derive(Debug, Default)]
+pub struct KeysoundTimeline(HashMap<Positions, Vec<(f64, SoundId)>>);
+
+impl KeysoundTimeline {
+    /// Adds a keysound to a lane, these have to be pushed in time order
+    pub fn push(&mut self, position: Positions, time: f64, id: SoundId) {
+        self.0.entry(position).or_default().push((time, id));
+    }
+
+    /// The last keysound placed in `position` at or before `secs`, along with its time
+    pub fn keysound_at(&self, position: Positions, secs: f64) -> Option<(f64, SoundId)> {
+        let keysounds = self.0.get(&position)?;
+        let index = keysounds.partition_point(|(time, _)| *time <= secs);
+
+        index.checked_sub(1).map(|index| keysounds[index])
+    }
+}
+
+/// A period where scrolling freezes (#STOP), in absolute seconds
+#[derive(Clone, Debug)]
+pub struct StopTime {
+    pub start_time: f64,
+    pub duration: f64,
+}
+
+/// What a chart is, as opposed to how it's played
+#[derive(Clone, Debug, Default)]
+pub struct ChartMeta {
+    pub title: String,
+    pub subtitle: String,
+    pub artist: String,
+    pub genre: String,
+    pub level: Option<u32>,
+    /// Name of the difficulty, like "HYPER" or "Challenge"
+    pub difficulty: Option<String>,
+    /// Gauge increase over the whole chart, from #TOTAL
+    pub total: Option<f64>,
+    /// Judge rank as the chart format states it
+    pub rank: Option<String>,
+    pub min_bpm: f64,
+    pub max_bpm: f64,
+    /// Number of notes that can be hit, not counting mines
+    pub note_count: usize,
+    /// Time from the start of the song to the end of the last note, in seconds
+    pub length: f64,
+    /// Name of the text encoding the chart was read in
+    pub encoding: &'static str,
+}
+
+impl ChartMeta {
+    /// Fills in the note count and length from the chart's notes
+    pub fn count_notes(&mut self, notes: &[NoteTime]) {
+        let playable = notes
+            .iter()
+            .filter(|note| !matches!(note.note_type, NoteType::Mine { .. }));
+
+        self.note_count = playable.clone().count();
+        self.length = playable
+            .map(|note| match note.note_type {
+                NoteType::Long { end_time } => end_time,
+                _ => note.spawn_time,
+            })
+            .fold(0., f64::max);
+    }
+
+    /// Title with the subtitle after it, if there is one
+    pub fn full_title(&self) -> String {
+        if self.subtitle.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} {}", self.title, self.subtitle)
+        }
+    }
+
+    /// Difficulty name and level, like "HYPER 10"
+    pub fn level_text(&self) -> String {
+        let level = self.level.map(|level| level.to_string());
+
+        [self.difficulty.clone(), level]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// A line with the genre, level, BPM, note count, length and judge settings, for showing
+    /// under the title
+    pub fn details_text(&self) -> String {
+        let details = format!(
+            "{}  BPM {}  {} notes  {}:{:02}",
+            self.level_text(),
+            self.bpm_text(),
+            self.note_count,
+            self.length as u32 / 60,
+            self.length as u32 % 60
+        );
+
+        [self.genre.clone(), details, self.judge_text()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    /// Judge rank and #TOTAL, like "JUDGE EASY  TOTAL 300"
+    pub fn judge_text(&self) -> String {
+        let rank = self.rank.as_ref().map(|rank| format!("JUDGE {}", rank));
+        let total = self.total.map(|total| format!("TOTAL {:.0}", total));
+
+        [rank, total]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    /// BPM for display, as a range if it changes
+    pub fn bpm_text(&self) -> String {
+        if self.min_bpm == self.max_bpm {
+            format!("{:.0}", self.max_bpm)
+        } else {
+            format!("{:.0}-{:.0}", self.min_bpm, self.max_bpm)
+        }
+    }
 }
 
 #[derive(Resource, Debug)]
 pub struct SongConfig {
     pub notes: Vec<NoteTime>,
     pub bgms: Vec<BGM>,
-    pub audio_handles: HashMap<ObjId, Handle<AudioSource>>,
+    pub stops: Vec<StopTime>,
+    pub layout: LaneLayout,
+    /// Seed the #RANDOM branches were picked with
+    pub random_seed: u64,
+    /// Value picked for each #RANDOM in the chart
+    pub random_values: Vec<u32>,
+    pub meta: ChartMeta,
+    pub invisible_keysounds: KeysoundTimeline,
+    pub audio_handles: HashMap<SoundId, Handle<AudioSource>>,
+    /// Part of the file each sound plays, for sounds that don't play a whole file
+    pub audio_slices: HashMap<SoundId, AudioSlice>,
+}
+impl SongConfig {
+    /// Config for formats without #RANDOM branches or invisible keysounds, whose sounds play
+    /// their whole file
+    pub fn new(
+        notes: Vec<NoteTime>,
+        bgms: Vec<BGM>,
+        stops: Vec<StopTime>,
+        layout: LaneLayout,
+        meta: ChartMeta,
+        audio_handles: HashMap<SoundId, Handle<AudioSource>>,
+    ) -> Self {
+        Self {
+            notes,
+            bgms,
+            stops,
+            layout,
+            random_seed: 0,
+            random_values: Vec::new(),
+            meta,
+            invisible_keysounds: KeysoundTimeline::default(),
+            audio_handles,
+            audio_slices: HashMap::new(),
+        }
+    }
+
+    /// Plays a sound, only the part of its file that its slice covers
+    pub fn play_sound(&self, audio: &Audio, id: SoundId) {
+        // Charts without keysounds (like osu!mania) leave their notes without a handle
+        let Some(audio_handle) = self.audio_handles.get(&id) else {
+            return;
+        };
+
+        let mut command = audio.play(audio_handle.clone());
+        command.with_volume(VOLUME);
+        if let Some(slice) = self.audio_slices.get(&id) {
+            command.start_from(slice.start);
+            if let Some(end) = slice.end {
+                command.end_at(end);
+            }
+        }
+    }
+
+    /// Converts song time to scroll time, which doesn't advance while a stop is in effect
+    pub fn scroll_time(&self, secs: f64) -> f64 {
+        let stopped: f64 = self
+            .stops
+            .iter()
+            .map(|stop| (secs - stop.start_time).clamp(0., stop.duration))
+            .sum();
+
+        secs - stopped
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Position {}
 
-pub fn load_config(file_path: &str, asset_server: &AssetServer) -> SongConfig {
+/// Finds the lane an object is played in, or the reason it can't be played
+fn classify_note(note: &Obj, layout: &LaneLayout) -> Result<Positions, &'static str> {
+    if note.key == Key::FreeZone {
+        return Err("free zone");
+    }
+
+    layout
+        .channel_map
+        .get(&(note.is_player1, note.key))
+        .copied()
+        .ok_or("no lane in this layout")
+}
+
+/// The BMS channel an object was placed on (like "11" or "D6"), for warnings
+fn channel_name(note: &Obj) -> String {
+    let kind = match (&note.kind, note.is_player1) {
+        (NoteKind::Visible, true) => '1',
+        (NoteKind::Visible, false) => '2',
+        (NoteKind::Invisible, true) => '3',
+        (NoteKind::Invisible, false) => '4',
+        (NoteKind::Long, true) => '5',
+        (NoteKind::Long, false) => '6',
+        (NoteKind::Landmine, true) => 'D',
+        (NoteKind::Landmine, false) => 'E',
+    };
+
+    let lane = match note.key {
+        Key::Key1 => '1',
+        Key::Key2 => '2',
+        Key::Key3 => '3',
+        Key::Key4 => '4',
+        Key::Key5 => '5',
+        Key::Scratch => '6',
+        Key::FreeZone => '7',
+        Key::Key6 => '8',
+        Key::Key7 => '9',
+    };
+
+    format!("{}{}", kind, lane)
+}
+
+/// Name of a #DIFFICULTY value
+fn difficulty_name(difficulty: u32) -> Option<&'static str> {
+    match difficulty {
+        1 => Some("BEGINNER"),
+        2 => Some("NORMAL"),
+        3 => Some("HYPER"),
+        4 => Some("ANOTHER"),
+        5 => Some("INSANE"),
+        _ => None,
+    }
+}
+
+/// Name of a #RANK judge level
+fn rank_name(rank: &JudgeLevel) -> &'static str {
+    match rank {
+        JudgeLevel::VeryHard => "VERY HARD",
+        JudgeLevel::Hard => "HARD",
+        JudgeLevel::Normal => "NORMAL",
+        JudgeLevel::Easy => "EASY",
+    }
+}
+
+/// Gauge damage of a mine in percent, read from its object id the way LR2 does (ZZ is a full gauge)
+fn mine_damage(id: ObjId) -> f32 {
+    let value = u16::from(id);
+
+    if value == 36 * 36 - 1 {
+        100.
+    } else {
+        value as f32 / 2.
+    }
+}
+
+/// Loads the #WAV file of a sound the first time an object uses it. Sounds that get no handle
+/// are remembered as well, so each one is only resolved once.
+fn load_wav(
+    id: ObjId,
+    wav_files: &HashMap<ObjId, PathBuf>,
+    keysounds: &mut KeysoundResolver,
+    audio_handles: &mut HashMap<SoundId, Handle<AudioSource>>,
+    unloaded: &mut HashSet<SoundId>,
+) -> Result<(), ChartLoadError> {
+    let sound_id = SoundId::from(id);
+    if audio_handles.contains_key(&sound_id) || unloaded.contains(&sound_id) {
+        return Ok(());
+    }
+
+    let wav_file = wav_files.get(&id).ok_or(ChartLoadError::UndefinedWav(id))?;
+    if let Some(audio_handle) = keysounds.load(wav_file) {
+        audio_handles.insert(sound_id, audio_handle);
+    } else {
+        unloaded.insert(sound_id);
+    }
+
+    Ok(())
+}
+
+pub fn load_config(
+    file_path: &str,
+    asset_server: &AssetServer,
+    settings: &UserSettings,
+) -> Result<SongConfig, ChartLoadError> {
     // test parse file
     // let file_path = "[Cres.]endtime/end_time_n.bms";
     println!("Loading file_path={}", file_path);
-    let bms = new_bms_parser::new_parse(file_path);
-    let bpm = bms.header.bpm.unwrap();
+    let mut keysounds =
+        KeysoundResolver::new(file_path, &settings.keysound_extensions, asset_server);
+
+    let mut rng = ChartRng::from_time(settings.random_override);
+    let config = load_chart(file_path, &mut rng, &mut keysounds);
+    if let Ok(config) = &config {
+        println!("Decoded chart as {}", config.meta.encoding);
+        let level = config.meta.level_text();
+        if !level.is_empty() {
+            println!("Playing the {} chart", level);
+        }
+        println!(
+            "Using the {} lane layout ({} lanes)",
+            config.layout.name,
+            config.layout.lane_count()
+        );
+    }
+
+    keysounds.report_missing();
+    config
+}
+
+/// Seed #RANDOM branches are picked with when only reading a chart's metadata
+const META_RANDOM_SEED: u64 = 0;
+
+/// Reads a chart's metadata for song select, without loading any of its sounds. #RANDOM branches
+/// are picked with a fixed seed, so the same chart always gets the same metadata.
+pub fn load_meta(file_path: &str, settings: &UserSettings) -> Result<ChartMeta, ChartLoadError> {
+    let mut keysounds = KeysoundResolver::without_loading(file_path, &settings.keysound_extensions);
+    let mut rng = ChartRng::new(META_RANDOM_SEED, settings.random_override);
+
+    Ok(load_chart(file_path, &mut rng, &mut keysounds)?.meta)
+}
+
+fn load_chart(
+    file_path: &str,
+    rng: &mut ChartRng,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    let chart_format = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match chart_format.as_str() {
+        "bmson" => bmson::load_config(file_path, keysounds),
+        "osu" => osu::load_config(file_path, keysounds),
+        "sm" | "ssc" => stepmania::load_config(file_path, keysounds),
+        _ => load_bms_config(file_path, &chart_format, rng, keysounds),
+    }
+}
+
+fn load_bms_config(
+    file_path: &str,
+    chart_format: &str,
+    rng: &mut ChartRng,
+    keysounds: &mut KeysoundResolver,
+) -> Result<SongConfig, ChartLoadError> {
+    let parsed = new_bms_parser::new_parse(file_path, rng, keysounds)?;
+    let bms = parsed.bms;
+    // A zero or negative #BPM can't time anything, so it counts as missing
+    let bpm = bms
+        .header
+        .bpm
+        .filter(|bpm| *bpm > 0.)
+        .ok_or(ChartLoadError::MissingBpm)?;
+    let timing_map = TimingMap::from_bms(&bms, bpm);
+    let (min_bpm, max_bpm) = timing_map.bpm_range();
+    let mut meta = ChartMeta {
+        title: bms.header.title.clone().unwrap_or_default(),
+        subtitle: bms.header.subtitle.clone().unwrap_or_default(),
+        artist: bms.header.artist.clone().unwrap_or_default(),
+        genre: bms.header.genre.clone().unwrap_or_default(),
+        level: bms.header.play_level.map(u32::from),
+        difficulty: bms
+            .header
+            .difficulty
+            .and_then(|difficulty| difficulty_name(difficulty as u32))
+            .map(String::from),
+        total: bms.header.total,
+        rank: bms
+            .header
+            .rank
+            .as_ref()
+            .map(|rank| rank_name(rank).to_string()),
+        min_bpm,
+        max_bpm,
+        encoding: parsed.encoding.name(),
+        ..default()
+    };
     let notes = bms.notes;
-    let num_measures = notes.last_obj_time().unwrap().track.0 as f64; // can panic
+
+    // Invisible objects and mines only matter in lanes with notes, so they don't pick the layout
+    let channels: HashSet<(bool, Key)> = notes
+        .all_notes()
+        .into_iter()
+        .filter(|note| matches!(note.kind, NoteKind::Visible | NoteKind::Long))
+        .map(|note| (note.is_player1, note.key))
+        .collect();
+    let layout = LaneLayout::detect(chart_format, bms.header.player.as_ref(), &channels);
+
+    // Encoding detection tries each candidate's #PATH_WAV, so always set the one that was picked
+    if let Some(wav_path_root) = &bms.header.wav_path_root {
+        println!("Loading sounds from #PATH_WAV {:?}", wav_path_root);
+    }
+    keysounds.set_sound_path(bms.header.wav_path_root.as_deref());
 
     // TODO load sound and add to notetimes?
     let wav_files_map = bms.header.wav_files;
 
-    // this is wrong i think
-    // TODO if we assume all 4/4, then number of measure * 4 = number of beats
-    //      then number of beats / bpm = length of song in seconds
-    let num_beats = num_measures * 4.;
-    let song_length = (num_beats / bpm) * 60.;
-    println!("Num beats: {}", num_beats);
-    println!("Song length: {}", song_length);
-    // let measure_time = (bpm / num_measures) * 0.75;
-    let measure_time = song_length / num_measures;
-    println!("Measure length: {}", measure_time);
-
-    let mut audio_handles_map: HashMap<ObjId, Handle<AudioSource>> = HashMap::new();
+    let mut audio_handles_map: HashMap<SoundId, Handle<AudioSource>> = HashMap::new();
+    // Sounds that were looked for but got no handle, so they aren't looked for again
+    let mut unloaded_sounds: HashSet<SoundId> = HashSet::new();
 
     let mut wavs_vec: Vec<(&ObjId, &PathBuf)> = wav_files_map.iter().collect();
     wavs_vec.sort_by_key(|&(key, _value)| key);
@@ -127,71 +530,89 @@ pub fn load_config(file_path: &str, asset_server: &AssetServer) -> SongConfig {
     // println!("sorted_wavs: {:#?}", wavs_vec);
 
     let mut notetimes: Vec<NoteTime> = Vec::new();
+
+    // Objects that can't be played, counted by channel and the reason they were skipped
+    let mut skipped_channels: BTreeMap<(String, &str), usize> = BTreeMap::new();
+
+    // Index into `notetimes` of the long note head waiting for its end in each lane
+    let mut ln_heads: HashMap<Positions, usize> = HashMap::new();
+    // Index into `notetimes` of the last note in each lane, for #LNOBJ end markers
+    let mut last_notes: HashMap<Positions, usize> = HashMap::new();
+    let mut invisible_keysounds = KeysoundTimeline::default();
+
     for note in notes.all_notes() {
         // if let 0..=20 = note.offset.track.0 {
         // println!("note: {:#?}", note);
         // }
 
-        // load sound file if not already loaded
-        let wav_id = note.obj;
-        if !audio_handles_map.contains_key(&wav_id) {
-            // let wav_file = wav_files_map.get(&wav_id).unwrap().to_str().unwrap();
-            let wav_file = wav_files_map
-                .get(&wav_id)
-                .clone()
-                .expect("Failed to get wav_file from map");
-            // let path = format!("songs/{}", file_path);
-            let path_buf = PathBuf::from(file_path);
-            let parent_path = path_buf
-                .parent()
-                .to_owned()
-                .expect("could not find parent path");
-            let mut wav_path = parent_path.join(&wav_file);
-            let mut check_path_str = format!("assets/{}", wav_path.clone().to_str().unwrap());
-            let mut check_path = PathBuf::from(check_path_str);
-
-            if !check_path.exists() {
-                // println!("{:?} does not exist 1", check_path);
-                wav_path.set_extension("ogg");
-                check_path_str = format!("assets/{}", wav_path.clone().to_str().unwrap());
-                check_path = PathBuf::from(check_path_str);
-
-                if !check_path.exists() {
-                    println!("ogg {:?} does not exist", check_path);
-                }
+        let key = match classify_note(note, &layout) {
+            Ok(key) => key,
+            Err(reason) => {
+                *skipped_channels
+                    .entry((channel_name(note), reason))
+                    .or_default() += 1;
+                continue;
             }
+        };
+
+        let spawn_time = timing_map.seconds_at(&note.offset);
+        let wav_id = note.obj;
+        let sound_id = SoundId::from(wav_id);
+
+        // Mines don't have a sound, their object id encodes the damage instead
+        if note.kind == NoteKind::Landmine {
+            notetimes.push(NoteTime {
+                spawn_time,
+                position: key,
+                note_type: NoteType::Mine {
+                    damage: mine_damage(wav_id),
+                },
+                audio_source_id: sound_id,
+            });
+            continue;
+        }
 
-            let wav_handle: Handle<AudioSource> = asset_server.load(wav_path);
-            audio_handles_map.insert(wav_id.to_owned(), wav_handle);
+        // An #LNOBJ object turns the previous note in its lane into a long note ending here
+        if note.kind == NoteKind::Visible && parsed.lnobj_ids.contains(&wav_id) {
+            if let Some(&index) = last_notes.get(&key) {
+                notetimes[index].note_type = NoteType::Long {
+                    end_time: spawn_time,
+                };
+            }
+            continue;
         }
 
-        // determine spawn time based on measure, numerator, denominator -- probably wrong
-        let numerator = note.offset.numerator;
-        let denominator = note.offset.denominator;
-        let measure = note.offset.track.0;
-
-        // this is also quite stupid
-        let key = match note.key {
-            bms_rs::lex::command::Key::Key1 => Positions::One,
-            bms_rs::lex::command::Key::Key2 => Positions::Two,
-            bms_rs::lex::command::Key::Key3 => Positions::Three,
-            bms_rs::lex::command::Key::Key4 => Positions::Four,
-            bms_rs::lex::command::Key::Key5 => Positions::Five,
-            bms_rs::lex::command::Key::Key6 => Positions::Six,
-            bms_rs::lex::command::Key::Key7 => Positions::Seven,
-            bms_rs::lex::command::Key::Scratch => Positions::Scratch,
-            _ => Positions::One,
-        };
+        // Long notes on channels 5x/6x come in pairs, the second one marks the end
+        if note.kind == NoteKind::Long {
+            if let Some(index) = ln_heads.remove(&key) {
+                notetimes[index].note_type = NoteType::Long {
+                    end_time: spawn_time,
+                };
+                continue;
+            }
+            ln_heads.insert(key, notetimes.len());
+        }
 
-        // TODO does note_offset also need to depend on bpm? time signature? etc?
-        let start_time = measure as f64 * measure_time;
-        let note_offset = (numerator as f64 / denominator as f64) * measure_time;
-        let spawn_time = (start_time + note_offset) as f64;
+        load_wav(
+            wav_id,
+            &wav_files_map,
+            keysounds,
+            &mut audio_handles_map,
+            &mut unloaded_sounds,
+        )?;
+
+        // Invisible notes are never played, they only set the sound of presses between notes
+        if note.kind == NoteKind::Invisible {
+            invisible_keysounds.push(key, spawn_time, sound_id);
+            continue;
+        }
 
+        last_notes.insert(key, notetimes.len());
         notetimes.push(NoteTime {
             spawn_time,
             position: key,
-            audio_source_id: wav_id,
+            note_type: NoteType::Normal,
+            audio_source_id: sound_id,
         });
     }
 
@@ -202,58 +623,48 @@ pub fn load_config(file_path: &str, asset_server: &AssetServer) -> SongConfig {
         let time = bgm.0;
         let obj_ids = bgm.1;
 
-        // Determine spawn time
-        // TODO spawn timing is definitely off
-        //      if there are blank notes in the measures, we can determine time signature
-        let measure = time.track.0;
-        let start_time = measure as f64 * measure_time;
-        let note_offset = (time.numerator as f64 / time.denominator as f64) * measure_time;
-        let spawn_time = (start_time + note_offset) as f64;
+        let spawn_time = timing_map.seconds_at(time);
 
         for id in obj_ids {
-            if !audio_handles_map.contains_key(&id) {
-                // println!("objid {:#?}", &id);
-                // let default = PathBuf::from(r"bass_A#1.wav");
-                let wav_file = wav_files_map.get(&id).clone().unwrap(); //_or(&default);
-                let path_buf = PathBuf::from(file_path);
-                let parent_path = path_buf
-                    .parent()
-                    .to_owned()
-                    .expect("could not find parent path");
-                let mut wav_path = parent_path.join(&wav_file);
-                let mut check_path_str = format!("assets/{}", wav_path.clone().to_str().unwrap());
-                let mut check_path = PathBuf::from(check_path_str);
-
-                if !check_path.exists() {
-                    // println!("{:?} does not exist 2", check_path);
-                    wav_path.set_extension("ogg");
-                    check_path_str = format!("assets/{}", wav_path.clone().to_str().unwrap());
-                    check_path = PathBuf::from(check_path_str);
-
-                    if !check_path.exists() {
-                        println!("ogg {:?} does not exist", check_path);
-                    }
-                }
-
-                let wav_handle: Handle<AudioSource> = asset_server.load(wav_path);
-                audio_handles_map.insert(id.to_owned(), wav_handle);
-            }
+            load_wav(
+                *id,
+                &wav_files_map,
+                keysounds,
+                &mut audio_handles_map,
+                &mut unloaded_sounds,
+            )?;
         }
 
         bgms_config_list.push(BGM {
             spawn_time,
-            audio_source_ids: obj_ids.clone(),
+            audio_source_ids: obj_ids.iter().map(|id| SoundId::from(*id)).collect(),
         });
     }
 
-    println!("bpm: {}", bpm);
-    println!("num_measures: {}", num_measures);
-    println!("wav_path_root: {:#?}", bms.header.wav_path_root);
+    for ((channel, reason), count) in &skipped_channels {
+        println!(
+            "Warning: skipped {} objects on channel {} ({})",
+            count, channel, reason
+        );
+    }
+
+    if notetimes.is_empty() {
+        return Err(ChartLoadError::EmptyChart);
+    }
+
+    meta.count_notes(&notetimes);
 
     // println!("NoteTimes: {:#?}", notetimes);
-    SongConfig {
+    Ok(SongConfig {
         notes: notetimes,
         bgms: bgms_config_list,
+        stops: timing_map.stop_times(),
+        layout,
+        random_seed: rng.seed(),
+        random_values: rng.picked().to_vec(),
+        meta,
+        invisible_keysounds,
         audio_handles: audio_handles_map,
-    }
+        audio_slices: HashMap::new(),
+    })
 }
@@ -1,5 +1,8 @@
-use crate::bars::GameplayUI;
+use crate::bars::{song_time, GameplayUI};
+use crate::consts::TARGET_POSITION;
+use crate::score::Timing;
 use crate::time::ControlledTime;
+use crate::types::SongConfig;
 use crate::ScoreResource;
 use bevy::prelude::*;
 
@@ -7,6 +10,7 @@ fn setup_ui(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
+    song_config: Res<SongConfig>,
 ) {
     let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
     let color = color_materials.add(Color::NONE);
@@ -20,6 +24,7 @@ fn setup_ui(
                 top: Val::Px(10.),
                 // ..default()
                 // },
+                flex_direction: FlexDirection::Column,
                 ..default()
             },
             // border_color: color,
@@ -39,6 +44,25 @@ fn setup_ui(
                     ..default()
                 })
                 .insert(TimeText);
+
+            let meta = &song_config.meta;
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    format!(
+                        "{} / {}\n{}  {}",
+                        meta.full_title(),
+                        meta.artist,
+                        meta.details_text(),
+                        meta.encoding
+                    ),
+                    TextStyle {
+                        font_size: 20.0,
+                        font: font.clone(),
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                ),
+                ..default()
+            });
         })
         .insert(GameplayUI);
 
@@ -68,14 +92,70 @@ fn setup_ui(
                 .insert(ScoreText);
         })
         .insert(GameplayUI);
+
+    // Centered over the lanes
+    let lanes = &song_config.layout.lanes;
+    let lanes_x = lanes.iter().map(|lane| lane.x).sum::<f32>() / lanes.len().max(1) as f32;
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 30.0,
+                    font,
+                    color: Color::NONE,
+                },
+            ),
+            transform: Transform::from_translation(Vec3::new(lanes_x, TARGET_POSITION + 60., 2.)),
+            ..default()
+        })
+        .insert(TimingText::default())
+        .insert(GameplayUI);
 }
 
 #[derive(Component)]
 struct TimeText;
 
+/// Seconds the FAST/SLOW indicator takes to fade out after a hit
+const TIMING_TEXT_SECONDS: f32 = 0.5;
+
+/// Shows whether the latest hit was FAST or SLOW, just above the judge line
+#[derive(Component, Default)]
+struct TimingText {
+    /// Number of hits judged when the text was last shown, to tell when a new one comes in
+    judged: usize,
+    /// When the text was last shown, in seconds since startup
+    shown_at: f64,
+}
+
+fn update_timing_text(
+    score: Res<ScoreResource>,
+    time: Res<ControlledTime>,
+    mut query: Query<(&mut Text, &mut TimingText)>,
+) {
+    let now = time.seconds_since_startup();
+
+    for (mut text, mut timing_text) in query.iter_mut() {
+        if score.offsets.len() != timing_text.judged {
+            timing_text.judged = score.offsets.len();
+            timing_text.shown_at = now;
+        }
+
+        let (value, color) = match score.last_timing {
+            Some(Timing::Fast) => ("FAST", Color::rgb(0.3, 0.6, 1.)),
+            Some(Timing::Slow) => ("SLOW", Color::rgb(1., 0.4, 0.3)),
+            None => ("", Color::NONE),
+        };
+        let shown_for = (now - timing_text.shown_at) as f32;
+        let alpha = (1. - shown_for / TIMING_TEXT_SECONDS).max(0.);
+
+        text.sections[0].value = value.to_string();
+        text.sections[0].style.color = color.with_a(color.a() * alpha);
+    }
+}
+
 fn update_time_text(time: Res<ControlledTime>, mut query: Query<(&mut Text, &TimeText)>) {
-    // Song starts 3 seconds after real time
-    let secs = time.seconds_since_startup() - 3.;
+    let secs = song_time(&time);
 
     // Don't do anything before the song starts
     if secs < 0. {
@@ -93,14 +173,15 @@ struct ScoreText;
 fn update_score_text(score: Res<ScoreResource>, mut query: Query<(&mut Text, &ScoreText)>) {
     for (mut text, _marker) in query.iter_mut() {
         text.sections[0].value = format!(
-            "Score: {}, PG: {} GR: {}: GD: {}: BD: {}: PR: {}, Fails: {}",
+            "Score: {}, PG: {} GR: {}: GD: {}: BD: {}: PR: {}, Fails: {}, Gauge: {:.0}%",
             score.score(),
             score.pgreats,
             score.greats,
             score.goods,
             score.bads,
             score.poors,
-            score.fails()
+            score.fails(),
+            score.gauge.value()
         );
     }
 }
@@ -121,7 +202,8 @@ impl<S: States> Plugin for UIPlugin<S> {
         app.add_systems(OnEnter(self.state.clone()), setup_ui);
         app.add_systems(
             Update,
-            (update_time_text, update_score_text).run_if(in_state(self.state.clone())),
+            (update_time_text, update_score_text, update_timing_text)
+                .run_if(in_state(self.state.clone())),
         );
         app.add_systems(OnExit(self.state.clone()), despawn_ui);
     }
@@ -1,15 +1,38 @@
+use crate::score::JudgeWindows;
 use bevy::prelude::*;
 
+/// How long notes are judged
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LnMode {
+    /// Only the head is judged, the note just has to be held until the end
+    Ln,
+    /// Charge note, the release is judged against the tail as well
+    Cn,
+    /// Hell charge note, like CN but the gauge keeps changing for as long as the note lasts
+    Hcn,
+}
+
 #[derive(Resource)]
 pub struct UserSettings {
     pub scroll_speed: f32,
     pub autoplay_enabled: bool,
+    pub ln_mode: LnMode,
+    /// Timing windows hits are judged with, in milliseconds
+    pub judge_windows: JudgeWindows,
+    /// Branch to play in charts with #RANDOM instead of a random one, for practice
+    pub random_override: Option<u32>,
+    /// Extensions tried in order when a chart's sound file isn't found under its own
+    pub keysound_extensions: Vec<String>,
 }
 impl Default for UserSettings {
     fn default() -> Self {
         Self {
             scroll_speed: 800.,
             autoplay_enabled: true,
+            ln_mode: LnMode::Ln,
+            judge_windows: JudgeWindows::default(),
+            random_override: None,
+            keysound_extensions: ["wav", "ogg", "flac", "mp3"].map(String::from).to_vec(),
         }
     }
 }